@@ -0,0 +1,73 @@
+/*
+ * C interface to the game of life engine, built by the game_of_life_ffi crate
+ * as libgame_of_life_ffi.so, .dylib or .dll and a static library.
+ *
+ * Coordinates have y going up. Every function that can fail returns a
+ * GolStatus, and gol_last_error_message() describes the last invalid argument,
+ * parse error or panic on the calling thread. A GolGame is only safe to use
+ * from one thread at a time.
+ */
+
+#ifndef GAME_OF_LIFE_H
+#define GAME_OF_LIFE_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum GolStatus {
+    GOL_OK = 0,
+    GOL_NULL_POINTER = 1,
+    GOL_INVALID_ARGUMENT = 2,
+    GOL_PARSE_ERROR = 3,
+    /* The game has no live cells. */
+    GOL_EMPTY = 4,
+    /* The engine panicked, the game may be left half updated. */
+    GOL_PANIC = 5
+} GolStatus;
+
+typedef struct GolGame GolGame;
+
+/* Called with each live cell, returning non-zero stops the iteration. */
+typedef int (*GolCellCallback)(int64_t x, int64_t y, void *user_data);
+
+/* A new empty game on the plane with the Conway rule, or NULL. */
+GolGame *gol_game_new(void);
+void gol_game_free(GolGame *game);
+
+/* A rule such as "B3/S23", and a Golly bounded grid such as "T64,48" or "" for
+ * the unbounded plane. */
+GolStatus gol_game_set_rule(GolGame *game, const char *rule);
+GolStatus gol_game_set_topology(GolGame *game, const char *topology);
+
+GolStatus gol_game_set_cell(GolGame *game, int64_t x, int64_t y, int alive);
+GolStatus gol_game_get_cell(const GolGame *game, int64_t x, int64_t y, int *alive);
+
+/* Evolves the game, with hashlife on the unbounded plane and one generation at
+ * a time on a bounded grid, which takes time in proportion to generations.
+ * GOL_INVALID_ARGUMENT, leaving the game as it was, if the pattern grows too
+ * far from the origin. */
+GolStatus gol_game_step(GolGame *game, uint64_t generations);
+GolStatus gol_game_generation(const GolGame *game, uint64_t *generation);
+GolStatus gol_game_population(const GolGame *game, uint64_t *population);
+/* GOL_EMPTY, leaving the bounds untouched, if there are no live cells. */
+GolStatus gol_game_bounds(const GolGame *game, int64_t *left, int64_t *bottom, int64_t *right, int64_t *top);
+GolStatus gol_game_cells(const GolGame *game, GolCellCallback callback, void *user_data);
+
+/* Reads RLE or any other supported pattern format into a new game. */
+GolStatus gol_game_load(const char *text, GolGame **game);
+/* Writes the game as RLE into a string to free with gol_string_free, or
+ * GOL_INVALID_ARGUMENT if a cell is at y = INT64_MIN, which RLE can't place. */
+GolStatus gol_game_save_rle(const GolGame *game, char **rle);
+void gol_string_free(char *text);
+
+/* Valid until the next failing call on this thread, or NULL. */
+const char *gol_last_error_message(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
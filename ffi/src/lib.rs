@@ -0,0 +1,373 @@
+//! A C ABI for the engine, declared in `include/game_of_life.h`. Games are
+//! opaque handles, every function reports failure with a `GolStatus` and no
+//! panic crosses the boundary.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use game_of_life::game::Game;
+use game_of_life::node::{Coord, Node};
+use game_of_life::rule::Rule;
+use game_of_life::topology::Topology;
+
+/// The header declares coordinates as `int64_t`, which breaks with the
+/// `wide-coordinates` feature.
+const _: fn(Coord) -> i64 = |coord| coord;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GolStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidArgument = 2,
+    ParseError = 3,
+    Empty = 4,
+    Panic = 5,
+}
+
+/// The opaque handle behind `GolGame *`.
+pub struct GolGame {
+    game: Game,
+    generation: u64,
+}
+
+/// Called with each live cell, returning non-zero stops the iteration.
+pub type GolCellCallback = Option<extern "C" fn(x: i64, y: i64, user_data: *mut c_void) -> c_int>;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl ToString) {
+    let message = CString::new(message.to_string().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(message));
+}
+
+/// Runs the body, turning a panic into `GolStatus::Panic`.
+fn guard(body: impl FnOnce() -> Result<(), GolStatus>) -> GolStatus {
+    match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => GolStatus::Ok,
+        Ok(Err(status)) => status,
+        Err(panic) => {
+            let message = panic.downcast_ref::<&str>().map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "panic".to_string());
+            set_last_error(message);
+            GolStatus::Panic
+        }
+    }
+}
+
+unsafe fn get_game<'a>(game: *const GolGame) -> Result<&'a GolGame, GolStatus> {
+    game.as_ref().ok_or(GolStatus::NullPointer)
+}
+
+unsafe fn get_game_mut<'a>(game: *mut GolGame) -> Result<&'a mut GolGame, GolStatus> {
+    game.as_mut().ok_or(GolStatus::NullPointer)
+}
+
+unsafe fn get_str<'a>(text: *const c_char) -> Result<&'a str, GolStatus> {
+    if text.is_null() {
+        return Err(GolStatus::NullPointer);
+    }
+    CStr::from_ptr(text).to_str().map_err(|error| {
+        set_last_error(error);
+        GolStatus::InvalidArgument
+    })
+}
+
+unsafe fn write<T>(out: *mut T, value: T) -> Result<(), GolStatus> {
+    if out.is_null() {
+        return Err(GolStatus::NullPointer);
+    }
+    out.write(value);
+    Ok(())
+}
+
+/// A new empty game on the plane with the Conway rule.
+#[no_mangle]
+pub extern "C" fn gol_game_new() -> *mut GolGame {
+    catch_unwind(|| Box::into_raw(Box::new(GolGame { game: Game::new(), generation: 0 })))
+        .unwrap_or(ptr::null_mut())
+}
+
+/// # Safety
+/// `game` must be null or a handle from this library that isn't used again.
+#[no_mangle]
+pub unsafe extern "C" fn gol_game_free(game: *mut GolGame) {
+    if !game.is_null() {
+        let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(game))));
+    }
+}
+
+/// # Safety
+/// `game` must be a live handle and `rule` a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn gol_game_set_rule(game: *mut GolGame, rule: *const c_char) -> GolStatus {
+    guard(|| {
+        let game = get_game_mut(game)?;
+        game.game.rule = get_str(rule)?.parse::<Rule>().map_err(|error| {
+            set_last_error(error);
+            GolStatus::ParseError
+        })?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// `game` must be a live handle and `topology` a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn gol_game_set_topology(game: *mut GolGame, topology: *const c_char) -> GolStatus {
+    guard(|| {
+        let game = get_game_mut(game)?;
+        game.game.topology = get_str(topology)?.parse::<Topology>().map_err(|error| {
+            set_last_error(error);
+            GolStatus::ParseError
+        })?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// `game` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn gol_game_set_cell(game: *mut GolGame, x: i64, y: i64, alive: c_int) -> GolStatus {
+    guard(|| {
+        let game = &mut get_game_mut(game)?.game;
+        if game.is_node_alive(x, y) != (alive != 0) {
+            game.toggle(&Node { x, y });
+        }
+        Ok(())
+    })
+}
+
+/// # Safety
+/// `game` must be a live handle and `alive` writable.
+#[no_mangle]
+pub unsafe extern "C" fn gol_game_get_cell(game: *const GolGame, x: i64, y: i64, alive: *mut c_int) -> GolStatus {
+    guard(|| {
+        let game = get_game(game)?;
+        write(alive, game.game.is_node_alive(x, y) as c_int)
+    })
+}
+
+/// Evolves the game, with hashlife on the unbounded plane, leaving it as it
+/// was if the pattern grows too far from the origin.
+///
+/// # Safety
+/// `game` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn gol_game_step(game: *mut GolGame, generations: u64) -> GolStatus {
+    guard(|| {
+        let game = get_game_mut(game)?;
+        game.game.step(generations).map_err(|error| {
+            set_last_error(error);
+            GolStatus::InvalidArgument
+        })?;
+        game.generation = game.generation.saturating_add(generations);
+        Ok(())
+    })
+}
+
+/// # Safety
+/// `game` must be a live handle and `generation` writable.
+#[no_mangle]
+pub unsafe extern "C" fn gol_game_generation(game: *const GolGame, generation: *mut u64) -> GolStatus {
+    guard(|| write(generation, get_game(game)?.generation))
+}
+
+/// # Safety
+/// `game` must be a live handle and `population` writable.
+#[no_mangle]
+pub unsafe extern "C" fn gol_game_population(game: *const GolGame, population: *mut u64) -> GolStatus {
+    guard(|| write(population, get_game(game)?.game.live_nodes.len() as u64))
+}
+
+/// The bounds of the live cells, or `GolStatus::Empty` without any.
+///
+/// # Safety
+/// `game` must be a live handle and the bounds writable.
+#[no_mangle]
+pub unsafe extern "C" fn gol_game_bounds(
+    game: *const GolGame,
+    left: *mut i64,
+    bottom: *mut i64,
+    right: *mut i64,
+    top: *mut i64,
+) -> GolStatus {
+    guard(|| {
+        let boundary = get_game(game)?.game.get_live_boundary().ok_or(GolStatus::Empty)?;
+        write(left, boundary.lower.x)?;
+        write(bottom, boundary.lower.y)?;
+        write(right, boundary.upper.x)?;
+        write(top, boundary.upper.y)
+    })
+}
+
+/// Calls the callback with each live cell until it returns non-zero.
+///
+/// # Safety
+/// `game` must be a live handle that the callback doesn't change.
+#[no_mangle]
+pub unsafe extern "C" fn gol_game_cells(game: *const GolGame, callback: GolCellCallback, user_data: *mut c_void) -> GolStatus {
+    guard(|| {
+        let game = get_game(game)?;
+        let callback = callback.ok_or(GolStatus::NullPointer)?;
+        for node in &game.game.live_nodes {
+            if callback(node.x, node.y, user_data) != 0 {
+                break;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Reads a pattern in any supported format into a new game.
+///
+/// # Safety
+/// `text` must be a NUL terminated string and `game` writable.
+#[no_mangle]
+pub unsafe extern "C" fn gol_game_load(text: *const c_char, game: *mut *mut GolGame) -> GolStatus {
+    guard(|| {
+        if game.is_null() {
+            return Err(GolStatus::NullPointer);
+        }
+        let pattern = Game::from_pattern(get_str(text)?).map_err(|error| {
+            set_last_error(error);
+            GolStatus::ParseError
+        })?;
+        write(game, Box::into_raw(Box::new(GolGame { game: pattern, generation: 0 })))
+    })
+}
+
+/// Writes the game as RLE into a new string for `gol_string_free`.
+///
+/// # Safety
+/// `game` must be a live handle and `rle` writable.
+#[no_mangle]
+pub unsafe extern "C" fn gol_game_save_rle(game: *const GolGame, rle: *mut *mut c_char) -> GolStatus {
+    guard(|| {
+        let text = get_game(game)?.game.to_rle().map_err(|error| {
+            set_last_error(error);
+            GolStatus::InvalidArgument
+        })?;
+        let text = CString::new(text).map_err(|error| {
+            set_last_error(error);
+            GolStatus::InvalidArgument
+        })?;
+        write(rle, text.into_raw())
+    })
+}
+
+/// # Safety
+/// `text` must be null or a string from this library that isn't used again.
+#[no_mangle]
+pub unsafe extern "C" fn gol_string_free(text: *mut c_char) {
+    if !text.is_null() {
+        drop(CString::from_raw(text));
+    }
+}
+
+/// The message of the last error on this thread, valid until the next call
+/// that fails, or null.
+#[no_mangle]
+pub extern "C" fn gol_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    extern "C" fn collect(x: i64, y: i64, user_data: *mut c_void) -> c_int {
+        let cells = unsafe { &mut *(user_data as *mut Vec<(i64, i64)>) };
+        cells.push((x, y));
+        0
+    }
+
+    #[test]
+    fn steps_a_glider_loaded_from_rle() {
+        unsafe {
+            let mut game = ptr::null_mut();
+            let rle = CString::new("bo$2bo$3o!").unwrap();
+            assert_eq!(gol_game_load(rle.as_ptr(), &mut game), GolStatus::Ok);
+            assert_eq!(gol_game_step(game, 4), GolStatus::Ok);
+            let (mut left, mut bottom, mut right, mut top) = (0, 0, 0, 0);
+            assert_eq!(gol_game_bounds(game, &mut left, &mut bottom, &mut right, &mut top), GolStatus::Ok);
+            assert_eq!((left, bottom, right, top), (1, -1, 3, 1));
+            let mut population = 0;
+            assert_eq!(gol_game_population(game, &mut population), GolStatus::Ok);
+            assert_eq!(population, 5);
+            let mut cells: Vec<(i64, i64)> = Vec::new();
+            let user_data = &mut cells as *mut Vec<(i64, i64)> as *mut c_void;
+            assert_eq!(gol_game_cells(game, Some(collect), user_data), GolStatus::Ok);
+            cells.sort();
+            assert_eq!(cells, vec![(1, -1), (2, -1), (2, 1), (3, -1), (3, 0)]);
+            let mut text = ptr::null_mut();
+            assert_eq!(gol_game_save_rle(game, &mut text), GolStatus::Ok);
+            assert!(CStr::from_ptr(text).to_str().unwrap().contains("bo$2bo$3o!"));
+            gol_string_free(text);
+            gol_game_free(game);
+        }
+    }
+
+    #[test]
+    fn sets_cells_and_rules() {
+        unsafe {
+            let game = gol_game_new();
+            assert_eq!(gol_game_set_cell(game, 3, -4, 1), GolStatus::Ok);
+            let mut alive = 0;
+            assert_eq!(gol_game_get_cell(game, 3, -4, &mut alive), GolStatus::Ok);
+            assert_eq!(alive, 1);
+            assert_eq!(gol_game_set_cell(game, 3, -4, 0), GolStatus::Ok);
+            let (mut left, mut bottom, mut right, mut top) = (0, 0, 0, 0);
+            assert_eq!(gol_game_bounds(game, &mut left, &mut bottom, &mut right, &mut top), GolStatus::Empty);
+            let rule = CString::new("B36/S23").unwrap();
+            assert_eq!(gol_game_set_rule(game, rule.as_ptr()), GolStatus::Ok);
+            assert_eq!((*game).game.rule.to_string(), "B36/S23");
+            gol_game_free(game);
+        }
+    }
+
+    #[test]
+    fn reports_errors_as_codes() {
+        unsafe {
+            let game = gol_game_new();
+            let rule = CString::new("B9").unwrap();
+            assert_eq!(gol_game_set_rule(game, rule.as_ptr()), GolStatus::ParseError);
+            assert!(!gol_last_error_message().is_null());
+            assert_eq!(gol_game_set_rule(game, ptr::null()), GolStatus::NullPointer);
+            assert_eq!(gol_game_step(ptr::null_mut(), 1), GolStatus::NullPointer);
+            assert_eq!(gol_game_set_cell(game, i64::MAX, 0, 1), GolStatus::Ok);
+            assert_eq!(gol_game_step(game, u64::MAX), GolStatus::InvalidArgument);
+            assert!(!gol_last_error_message().is_null());
+            assert_eq!(gol_game_set_cell(game, i64::MAX, 0, 0), GolStatus::Ok);
+            assert_eq!(gol_game_population(game, ptr::null_mut()), GolStatus::NullPointer);
+            assert_eq!(gol_game_cells(game, None, ptr::null_mut()), GolStatus::NullPointer);
+            let mut loaded = ptr::null_mut();
+            let text = CString::new("x = 1, y = 1, rule = nonsense\no!").unwrap();
+            assert_eq!(gol_game_load(text.as_ptr(), &mut loaded), GolStatus::ParseError);
+            assert!(loaded.is_null());
+            assert_eq!(guard(|| panic!("boom")), GolStatus::Panic);
+            assert_eq!(CStr::from_ptr(gol_last_error_message()).to_str().unwrap(), "boom");
+            gol_game_free(game);
+        }
+    }
+
+    /// Compiles `tests/header.c` against the header when a C compiler is
+    /// installed. CI also links and runs it.
+    #[test]
+    fn compiles_the_header() {
+        let manifest = env!("CARGO_MANIFEST_DIR");
+        let status = std::process::Command::new("cc")
+            .args(["-fsyntax-only", "-std=c99", "-Wall", "-Wextra", "-Werror", "-pedantic"])
+            .arg(format!("-I{}/include", manifest))
+            .arg(format!("{}/tests/header.c", manifest))
+            .status();
+        match status {
+            Ok(status) => assert!(status.success(), "tests/header.c doesn't compile"),
+            Err(error) => eprintln!("skipping the header check, no C compiler: {}", error),
+        }
+    }
+}
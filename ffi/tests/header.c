@@ -0,0 +1,54 @@
+/*
+ * Drives the library through include/game_of_life.h, so the header is checked
+ * against the exported functions. CI builds it as C and C++ and links it with
+ * the static library; cargo test only checks that it compiles.
+ */
+
+#include <stdio.h>
+#include <string.h>
+#include "game_of_life.h"
+
+#define CHECK(condition) \
+    do { \
+        if (!(condition)) { \
+            fprintf(stderr, "%s:%d: %s failed\n", __FILE__, __LINE__, #condition); \
+            return 1; \
+        } \
+    } while (0)
+
+static int count_cells(int64_t x, int64_t y, void *user_data) {
+    (void)x;
+    (void)y;
+    ++*(int *)user_data;
+    return 0;
+}
+
+int main(void) {
+    GolGame *game = NULL;
+    CHECK(gol_game_load("bo$2bo$3o!", &game) == GOL_OK);
+    CHECK(gol_game_step(game, 4) == GOL_OK);
+    uint64_t generation = 0;
+    uint64_t population = 0;
+    CHECK(gol_game_generation(game, &generation) == GOL_OK && generation == 4);
+    CHECK(gol_game_population(game, &population) == GOL_OK && population == 5);
+    int64_t left, bottom, right, top;
+    CHECK(gol_game_bounds(game, &left, &bottom, &right, &top) == GOL_OK);
+    CHECK(left == 1 && bottom == -1 && right == 3 && top == 1);
+    int cells = 0;
+    CHECK(gol_game_cells(game, count_cells, &cells) == GOL_OK && cells == 5);
+    char *rle = NULL;
+    CHECK(gol_game_save_rle(game, &rle) == GOL_OK && strstr(rle, "bo$2bo$3o!") != NULL);
+    gol_string_free(rle);
+    CHECK(gol_game_set_rule(game, "B9") == GOL_PARSE_ERROR && gol_last_error_message() != NULL);
+    CHECK(gol_game_set_topology(game, "T64,48") == GOL_OK);
+    CHECK(gol_game_set_cell(game, 10, 10, 1) == GOL_OK);
+    int alive = 0;
+    CHECK(gol_game_get_cell(game, 10, 10, &alive) == GOL_OK && alive);
+    gol_game_free(game);
+
+    GolGame *empty = gol_game_new();
+    CHECK(empty != NULL);
+    CHECK(gol_game_bounds(empty, &left, &bottom, &right, &top) == GOL_EMPTY);
+    gol_game_free(empty);
+    return 0;
+}
@@ -0,0 +1,187 @@
+use game_of_life::box_boundary::BoxBoundary;
+use game_of_life::game::Game;
+use game_of_life::node::{Coord, Node};
+use game_of_life::render::get_region_cells;
+use game_of_life::rule::Rule;
+use game_of_life::topology::Topology;
+use numpy::{PyArray2, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+fn value_error(error: impl ToString) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+/// A game of life: live cells with y up, a rule and a Golly bounded grid such
+/// as T64,48, or an empty topology for the unbounded plane.
+#[pyclass(name = "Game")]
+struct PyGame {
+    game: Game,
+    generation: u64,
+}
+
+#[pymethods]
+impl PyGame {
+    #[new]
+    #[pyo3(signature = (rule = "B3/S23", topology = ""))]
+    fn new(rule: &str, topology: &str) -> PyResult<Self> {
+        let mut game = Game::with_rule(rule.parse::<Rule>().map_err(value_error)?);
+        game.topology = topology.parse::<Topology>().map_err(value_error)?;
+        Ok(PyGame { game, generation: 0 })
+    }
+
+    /// Reads an RLE pattern, its bottom left cell at the origin unless it has
+    /// a `#CXRLE Pos` line.
+    #[staticmethod]
+    fn from_rle(text: &str) -> PyResult<Self> {
+        Ok(PyGame { game: Game::from_rle(text).map_err(value_error)?, generation: 0 })
+    }
+
+    /// Reads a pattern in any supported format.
+    #[staticmethod]
+    fn from_pattern(text: &str) -> PyResult<Self> {
+        Ok(PyGame { game: Game::from_pattern(text).map_err(value_error)?, generation: 0 })
+    }
+
+    fn to_rle(&self) -> PyResult<String> {
+        self.game.to_rle().map_err(value_error)
+    }
+
+    /// Makes the cells in a 2D array alive wherever it is true, row 0 at the
+    /// top and its top left cell at x, y. Raises ValueError if a cell would be
+    /// past the 64 bit coordinates.
+    #[staticmethod]
+    #[pyo3(signature = (array, x = 0, y = 0, rule = "B3/S23"))]
+    fn from_numpy(array: PyReadonlyArray2<'_, bool>, x: Coord, y: Coord, rule: &str) -> PyResult<Self> {
+        let mut game = Game::with_rule(rule.parse::<Rule>().map_err(value_error)?);
+        for ((row, column), &alive) in array.as_array().indexed_iter() {
+            if alive {
+                let node_x = Coord::try_from(column).ok().and_then(|column| x.checked_add(column));
+                let node_y = Coord::try_from(row).ok().and_then(|row| y.checked_sub(row));
+                match (node_x, node_y) {
+                    (Some(x), Some(y)) => game.live_nodes.push(Node { x, y }),
+                    _ => return Err(value_error(format!("cell {}, {} is out of range", column, row))),
+                }
+            }
+        }
+        Ok(PyGame { game, generation: 0 })
+    }
+
+    /// The cells of a region as a boolean array, row 0 at its top and column 0
+    /// at its left, by default the bounds of the live cells.
+    #[pyo3(signature = (left = None, bottom = None, right = None, top = None))]
+    fn to_numpy<'py>(
+        &self,
+        py: Python<'py>,
+        left: Option<Coord>,
+        bottom: Option<Coord>,
+        right: Option<Coord>,
+        top: Option<Coord>,
+    ) -> PyResult<Bound<'py, PyArray2<bool>>> {
+        let bounds = self.game.get_live_boundary().unwrap_or_else(BoxBoundary::new);
+        let region = BoxBoundary {
+            lower: Node {
+                x: left.unwrap_or(bounds.lower.x),
+                y: bottom.unwrap_or(bounds.lower.y),
+            },
+            upper: Node {
+                x: right.unwrap_or(bounds.upper.x),
+                y: top.unwrap_or(bounds.upper.y),
+            },
+        };
+        let cells = get_region_cells(&self.game, &region).map_err(value_error)?;
+        PyArray2::from_vec2(py, &cells).map_err(value_error)
+    }
+
+    #[getter]
+    fn get_rule(&self) -> String {
+        self.game.rule.to_string()
+    }
+
+    #[setter]
+    fn set_rule(&mut self, rule: &str) -> PyResult<()> {
+        self.game.rule = rule.parse::<Rule>().map_err(value_error)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn get_topology(&self) -> String {
+        self.game.topology.to_string()
+    }
+
+    #[setter]
+    fn set_topology(&mut self, topology: &str) -> PyResult<()> {
+        self.game.topology = topology.parse::<Topology>().map_err(value_error)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn population(&self) -> usize {
+        self.game.live_nodes.len()
+    }
+
+    /// How many generations the game has evolved.
+    #[getter]
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    #[pyo3(signature = (x, y, alive = true))]
+    fn set_cell(&mut self, x: Coord, y: Coord, alive: bool) {
+        if self.game.is_node_alive(x, y) != alive {
+            self.game.toggle(&Node { x, y });
+        }
+    }
+
+    fn clear_cell(&mut self, x: Coord, y: Coord) {
+        self.set_cell(x, y, false);
+    }
+
+    fn get_cell(&self, x: Coord, y: Coord) -> bool {
+        self.game.is_node_alive(x, y)
+    }
+
+    /// Kills every cell.
+    fn clear(&mut self) {
+        self.game.live_nodes.clear();
+    }
+
+    /// The live cells as (x, y) pairs.
+    fn cells(&self) -> Vec<(Coord, Coord)> {
+        self.game.live_nodes.iter().map(|node| (node.x, node.y)).collect()
+    }
+
+    /// The (left, bottom, right, top) bounds of the live cells, or None.
+    fn bounds(&self) -> Option<(Coord, Coord, Coord, Coord)> {
+        self.game.get_live_boundary().map(|BoxBoundary { lower, upper }| {
+            (lower.x, lower.y, upper.x, upper.y)
+        })
+    }
+
+    /// Evolves the game, with hashlife on the unbounded plane.
+    #[pyo3(signature = (generations = 1))]
+    fn evolve(&mut self, py: Python<'_>, generations: u64) -> PyResult<()> {
+        let game = &mut self.game;
+        py.detach(|| game.step(generations)).map_err(value_error)?;
+        self.generation = self.generation.saturating_add(generations);
+        Ok(())
+    }
+
+    fn __len__(&self) -> usize {
+        self.game.live_nodes.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Game(rule={:?}, topology={:?}, population={}, generation={})",
+            self.game.rule.to_string(), self.game.topology.to_string(), self.game.live_nodes.len(), self.generation,
+        )
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "game_of_life")]
+fn game_of_life_py(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyGame>()?;
+    Ok(())
+}
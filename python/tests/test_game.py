@@ -0,0 +1,57 @@
+import numpy as np
+import pytest
+
+import game_of_life
+
+GLIDER = "bo$2bo$3o!"
+
+
+def test_reads_and_writes_rle():
+    game = game_of_life.Game.from_rle(GLIDER)
+    assert game.population == len(game) == 5
+    assert sorted(game.cells()) == [(0, 0), (1, 0), (1, 2), (2, 0), (2, 1)]
+    assert game.bounds() == (0, 0, 2, 2)
+    assert game_of_life.Game.from_rle(game.to_rle()).cells() == game.cells()
+
+
+def test_evolves_with_and_without_hashlife():
+    game = game_of_life.Game.from_rle(GLIDER)
+    game.evolve()
+    game.evolve(3)
+    assert game.generation == 4
+    assert game.bounds() == (1, -1, 3, 1)
+    torus = game_of_life.Game.from_rle(GLIDER)
+    torus.topology = "T8,8"
+    torus.evolve(32)
+    assert torus.population == 5
+
+
+def test_sets_cells_rule_and_topology():
+    game = game_of_life.Game(rule="B36/S23", topology="T10,10")
+    game.set_cell(1, 2)
+    assert game.get_cell(1, 2)
+    game.clear_cell(1, 2)
+    assert not game.get_cell(1, 2)
+    game.set_cell(3, 4)
+    game.clear()
+    assert game.bounds() is None
+    assert (game.rule, game.topology) == ("B36/S23", "T10,10")
+    with pytest.raises(ValueError):
+        game.rule = "nonsense"
+    with pytest.raises(ValueError):
+        game_of_life.Game(topology="T2,2")
+
+
+def test_converts_numpy_arrays_with_row_0_at_the_top():
+    game = game_of_life.Game.from_rle(GLIDER)
+    cells = game.to_numpy()
+    expected = np.array([[False, True, False], [False, False, True], [True, True, True]])
+    assert cells.dtype == np.bool_
+    assert (cells == expected).all()
+    assert game.to_numpy(left=-1, bottom=0, right=0, top=1).tolist() == [[False, False], [False, True]]
+    copy = game_of_life.Game.from_numpy(cells, x=0, y=2)
+    assert sorted(copy.cells()) == sorted(game.cells())
+    with pytest.raises(ValueError):
+        game_of_life.Game.from_numpy(cells, x=2**63 - 2)
+    with pytest.raises(ValueError):
+        game_of_life.Game.from_numpy(cells, y=-(2**63) + 1)
@@ -0,0 +1,210 @@
+use std::fmt;
+use crate::canonical::get_orientations;
+use crate::game::Game;
+use crate::node::{Coord, Node};
+use crate::period::find_translation_phases;
+
+const DIGITS: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+const RUN_DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+const STRIP_HEIGHT: Coord = 5;
+
+#[derive(Debug, PartialEq)]
+pub struct ParseApgcodeError(String);
+
+impl fmt::Display for ParseApgcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid apgcode: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseApgcodeError {}
+
+fn push_zeros(code: &mut String, mut zeros: Coord) {
+    while zeros > 0 {
+        let run = zeros.min(39);
+        match run {
+            1 => code.push('0'),
+            2 => code.push('w'),
+            3 => code.push('x'),
+            _ => {
+                code.push('y');
+                code.push(RUN_DIGITS[(run - 4) as usize] as char);
+            }
+        }
+        zeros -= run;
+    }
+}
+
+/// Encodes normalized cells, y pointing down, in apgcode's extended Wechsler
+/// format: strips of five rows, one base 32 digit per column, `z` between
+/// strips and `w`, `x`, `y?` for runs of empty columns.
+pub fn encode_wechsler(cells: &[(Coord, Coord)]) -> String {
+    let width = cells.iter().map(|&(x, _)| x + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|&(_, y)| y + 1).max().unwrap_or(0);
+    let strips = (height + STRIP_HEIGHT - 1) / STRIP_HEIGHT;
+    let mut code = String::new();
+    for strip in 0..strips {
+        if strip > 0 {
+            code.push('z');
+        }
+        let mut columns = vec![0usize; width as usize];
+        for &(x, y) in cells {
+            if y / STRIP_HEIGHT == strip {
+                columns[x as usize] |= 1 << (y % STRIP_HEIGHT);
+            }
+        }
+        let mut zeros = 0;
+        for column in columns {
+            if column == 0 {
+                zeros += 1;
+            } else {
+                push_zeros(&mut code, zeros);
+                zeros = 0;
+                code.push(DIGITS[column] as char);
+            }
+        }
+    }
+    code
+}
+
+/// Decodes an extended Wechsler code into cells, y pointing down.
+pub fn decode_wechsler(code: &str) -> Result<Vec<(Coord, Coord)>, ParseApgcodeError> {
+    let error = || ParseApgcodeError(code.to_string());
+    let mut cells = Vec::new();
+    let (mut x, mut strip) = (0, 0);
+    let mut chars = code.chars();
+    while let Some(digit) = chars.next() {
+        match digit {
+            'z' => {
+                x = 0;
+                strip += 1;
+            }
+            'w' => x += 2,
+            'x' => x += 3,
+            'y' => {
+                let run = chars.next().ok_or_else(error)?;
+                let run = RUN_DIGITS.iter().position(|&c| c as char == run).ok_or_else(error)?;
+                x += 4 + run as Coord;
+            }
+            _ => {
+                let column = DIGITS.iter().position(|&c| c as char == digit).ok_or_else(error)?;
+                for bit in 0..STRIP_HEIGHT {
+                    if column & (1 << bit) != 0 {
+                        cells.push((x, strip * STRIP_HEIGHT + bit));
+                    }
+                }
+                x += 1;
+            }
+        }
+    }
+    Ok(cells)
+}
+
+/// The shortest, then alphabetically first, code of the cells in any
+/// orientation, which takes in the flip to y pointing down, or `None` if they
+/// span more than `Coord::MAX` in x or y.
+fn canonical_wechsler(game: &Game) -> Option<String> {
+    get_orientations(game.get_sorted_cells())?
+        .iter()
+        .map(|cells| encode_wechsler(cells))
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+}
+
+impl Game {
+    /// The Catagolue apgcode of a still life (`xs`), oscillator (`xp`) or
+    /// spaceship (`xq`), e.g. `xs4_33` for the block. Returns `None` for
+    /// patterns that die out or don't repeat within `max_period` generations.
+    pub fn apgcode(&self, max_period: usize) -> Option<String> {
+        if self.live_nodes.is_empty() {
+            return None;
+        }
+        let (phases, dx, dy) = find_translation_phases(self, max_period as u64)?;
+        let period = phases.len();
+        let code = phases.iter()
+            .map(canonical_wechsler)
+            .collect::<Option<Vec<String>>>()?
+            .into_iter()
+            .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))?;
+        let prefix = match (period, dx, dy) {
+            (1, 0, 0) => format!("xs{}", self.live_nodes.len()),
+            (_, 0, 0) => format!("xp{}", period),
+            _ => format!("xq{}", period),
+        };
+        Some(format!("{}_{}", prefix, code))
+    }
+
+    /// Builds the object an `xs`, `xp` or `xq` apgcode describes, with its top
+    /// left corner at the origin.
+    pub fn from_apgcode(apgcode: &str) -> Result<Game, ParseApgcodeError> {
+        let error = || ParseApgcodeError(apgcode.to_string());
+        let (prefix, code) = apgcode.split_once('_').ok_or_else(error)?;
+        let number = prefix.strip_prefix("xs")
+            .or_else(|| prefix.strip_prefix("xp"))
+            .or_else(|| prefix.strip_prefix("xq"))
+            .ok_or_else(error)?;
+        number.parse::<u64>().map_err(|_| error())?;
+        let mut game = Game::new();
+        game.live_nodes = decode_wechsler(code)?
+            .into_iter()
+            .map(|(x, y)| Node { x, y: -y })
+            .collect();
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn apgcode_of(rle: &str) -> Option<String> {
+        Game::from_rle(rle).unwrap().apgcode(30)
+    }
+
+    #[test]
+    fn encodes_still_lifes() {
+        assert_eq!(apgcode_of("2o$2o!").unwrap(), "xs4_33");
+        assert_eq!(apgcode_of("b2o$o2bo$b2o!").unwrap(), "xs6_696");
+        assert_eq!(apgcode_of("2o$obo$bo!").unwrap(), "xs5_253");
+        assert_eq!(apgcode_of("b2o$o2bo$bobo$2bo!").unwrap(), "xs7_2596");
+    }
+
+    #[test]
+    fn encodes_oscillators_in_their_smallest_phase() {
+        assert_eq!(apgcode_of("3o!").unwrap(), "xp2_7");
+        assert_eq!(apgcode_of("b3o$3o!").unwrap(), "xp2_7e");
+    }
+
+    #[test]
+    fn encodes_spaceships() {
+        assert_eq!(apgcode_of("bo$2bo$3o!").unwrap(), "xq4_153");
+        assert_eq!(apgcode_of("o2bo$4bo$o3bo$b4o!").unwrap(), "xq4_6frc");
+    }
+
+    #[test]
+    fn encodes_tall_objects_in_strips_with_runs_of_empty_columns() {
+        let mut game = Game::from_rle("2o$2o!").unwrap();
+        for node in Game::from_rle("2o$2o!").unwrap().live_nodes {
+            game.live_nodes.push(Node { x: node.x + 7, y: node.y - 6 });
+        }
+        assert_eq!(game.apgcode(2).unwrap(), "xs8_33zy2cc");
+        assert_eq!(encode_wechsler(&[(0, 0), (0, 6), (9, 6)]), "1z2y42");
+    }
+
+    #[test]
+    fn does_not_encode_dying_or_growing_patterns() {
+        assert_eq!(apgcode_of("o!"), None);
+        assert_eq!(apgcode_of("b2o$2o$bo!"), None);
+    }
+
+    #[test]
+    fn decodes_apgcodes() {
+        let glider = Game::from_apgcode("xq4_153").unwrap();
+        assert_eq!(glider.live_nodes.len(), 5);
+        assert_eq!(glider.apgcode(4).unwrap(), "xq4_153");
+        let pair = Game::from_apgcode("xs8_33zy2cc").unwrap();
+        assert_eq!(pair.apgcode(2).unwrap(), "xs8_33zy2cc");
+        assert!(Game::from_apgcode("xs4").is_err());
+        assert!(Game::from_apgcode("ov_s4").is_err());
+        assert!(Game::from_apgcode("xs4_3!").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use std::fs;
+use std::io::{self, Write};
+use std::process;
+use std::time::{Duration, Instant};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, MouseButton, MouseEventKind};
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use game_of_life::box_boundary::BoxBoundary;
+use game_of_life::game::Game;
+use game_of_life::node::{Coord, Node};
+use game_of_life::render::text::{to_text, TextStyle};
+use game_of_life::topology::Topology;
+
+const HELP: &str = "arrows pan  space play/pause  n step  b braille  +/- speed  click toggle  tab pick node  q quit";
+
+struct Tui {
+    game: Game,
+    style: TextStyle,
+    /// The node at the top left corner of the screen.
+    origin: Node,
+    playing: bool,
+    generation: u64,
+    speed: Duration,
+    /// Which node of a character a click toggles, counted along its rows from
+    /// the top left: the top or bottom half block, or one of the braille dots.
+    target: usize,
+}
+
+impl Tui {
+    /// The nodes the screen shows, leaving the last line for the status.
+    fn get_region(&self, columns: u16, rows: u16) -> BoxBoundary {
+        let (cell_columns, cell_rows) = self.style.get_cell_size();
+        let width = (columns as usize * cell_columns).max(1) as Coord;
+        let height = (rows.saturating_sub(1) as usize * cell_rows).max(1) as Coord;
+        BoxBoundary {
+            lower: Node { x: self.origin.x, y: self.origin.y.saturating_sub(height - 1) },
+            upper: Node { x: self.origin.x.saturating_add(width - 1), y: self.origin.y },
+        }
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let (columns, rows) = terminal::size()?;
+        let region = self.get_region(columns, rows);
+        let lines = to_text(&self.game, &region, self.style).unwrap_or_default();
+        queue!(out, Clear(ClearType::All))?;
+        for (row, line) in lines.iter().enumerate() {
+            queue!(out, MoveTo(0, row as u16), Print(line))?;
+        }
+        let status = format!(
+            "{} generation {} population {} at ({}, {}) every {}ms clicking {}  {}",
+            if self.playing { "playing" } else { "paused" },
+            self.generation, self.game.live_nodes.len(), self.origin.x, self.origin.y,
+            self.speed.as_millis(), self.get_target_name(), HELP,
+        );
+        let status: String = status.chars().take(columns as usize).collect();
+        queue!(out, MoveTo(0, rows.saturating_sub(1)), Print(status))?;
+        out.flush()
+    }
+
+    fn step(&mut self) {
+        self.game.evolve();
+        self.generation += 1;
+    }
+
+    fn pan(&mut self, dx: Coord, dy: Coord) {
+        let (cell_columns, cell_rows) = self.style.get_cell_size();
+        self.origin.x = self.origin.x.saturating_add(dx * cell_columns as Coord);
+        self.origin.y = self.origin.y.saturating_add(dy * cell_rows as Coord);
+    }
+
+    /// The column and row of the target node inside a character.
+    fn get_target_offset(&self) -> (usize, usize) {
+        let (cell_columns, cell_rows) = self.style.get_cell_size();
+        let target = self.target % (cell_columns * cell_rows);
+        (target % cell_columns, target / cell_columns)
+    }
+
+    fn get_target_name(&self) -> String {
+        match (self.style, self.get_target_offset()) {
+            (TextStyle::HalfBlock, (_, 0)) => "top".to_string(),
+            (TextStyle::HalfBlock, _) => "bottom".to_string(),
+            (TextStyle::Braille, (column, row)) => format!("dot {},{}", column + 1, row + 1),
+        }
+    }
+
+    /// The target node of the character at the column and row.
+    fn get_clicked_node(&self, column: u16, row: u16) -> Option<Node> {
+        let (cell_columns, cell_rows) = self.style.get_cell_size();
+        let (dx, dy) = self.get_target_offset();
+        let x = self.origin.x.checked_add((column as usize * cell_columns + dx) as Coord)?;
+        let y = self.origin.y.checked_sub((row as usize * cell_rows + dy) as Coord)?;
+        Some(Node { x, y })
+    }
+
+    fn toggle(&mut self, column: u16, row: u16) {
+        if let Some(node) = self.get_clicked_node(column, row) {
+            self.game.toggle(&node);
+        }
+    }
+
+    /// Handles one key, returning false to quit.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char(' ') => self.playing = !self.playing,
+            KeyCode::Char('n') => self.step(),
+            KeyCode::Char('b') => {
+                self.style = match self.style {
+                    TextStyle::HalfBlock => TextStyle::Braille,
+                    TextStyle::Braille => TextStyle::HalfBlock,
+                };
+                self.target = 0;
+            }
+            KeyCode::Tab => {
+                let (cell_columns, cell_rows) = self.style.get_cell_size();
+                self.target = (self.target + 1) % (cell_columns * cell_rows);
+            }
+            KeyCode::Char('+') => self.speed = (self.speed / 2).max(Duration::from_millis(10)),
+            KeyCode::Char('-') => self.speed = (self.speed * 2).min(Duration::from_secs(10)),
+            KeyCode::Left => self.pan(-4, 0),
+            KeyCode::Right => self.pan(4, 0),
+            KeyCode::Up => self.pan(0, 2),
+            KeyCode::Down => self.pan(0, -2),
+            _ => {}
+        }
+        true
+    }
+
+    fn run(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let mut last_step = Instant::now();
+        loop {
+            self.draw(out)?;
+            let timeout = if self.playing {
+                self.speed.saturating_sub(last_step.elapsed())
+            } else {
+                Duration::from_millis(250)
+            };
+            if event::poll(timeout)? {
+                match event::read()? {
+                    Event::Key(key) if key.kind == KeyEventKind::Press && !self.handle_key(key) => return Ok(()),
+                    Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+                        self.toggle(mouse.column, mouse.row);
+                    }
+                    _ => {}
+                }
+            }
+            if self.playing && last_step.elapsed() >= self.speed {
+                self.step();
+                last_step = Instant::now();
+            }
+        }
+    }
+}
+
+fn get_arg(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|index| args.get(index + 1))
+        .cloned()
+}
+
+fn load_game(args: &[String]) -> Result<Game, String> {
+    let mut game = match args.first().filter(|arg| !arg.starts_with('-')) {
+        Some(path) => {
+            let text = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+            Game::from_pattern(&text).map_err(|error| format!("{}: {}", path, error))?
+        }
+        None => Game::new(),
+    };
+    if let Some(topology) = get_arg(args, "--topology") {
+        game.topology = topology.parse::<Topology>().map_err(|error| error.to_string())?;
+    }
+    Ok(game)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let game = match load_game(&args) {
+        Ok(game) => game,
+        Err(message) => {
+            eprintln!("life-tui: {}", message);
+            process::exit(1);
+        }
+    };
+    let style = if args.iter().any(|arg| arg == "--braille") { TextStyle::Braille } else { TextStyle::HalfBlock };
+    let (columns, rows) = terminal::size().unwrap_or((80, 24));
+    let (cell_columns, cell_rows) = style.get_cell_size();
+    let origin = Node {
+        x: -((columns as usize * cell_columns / 2) as Coord),
+        y: (rows as usize * cell_rows / 2) as Coord,
+    };
+    let mut tui = Tui { game, style, origin, playing: false, generation: 0, speed: Duration::from_millis(200), target: 0 };
+    let mut out = io::stdout();
+    let result = terminal::enable_raw_mode()
+        .and_then(|_| execute!(out, EnterAlternateScreen, EnableMouseCapture, Hide))
+        .and_then(|_| tui.run(&mut out));
+    let _ = execute!(out, Show, DisableMouseCapture, LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+    if let Err(error) = result {
+        eprintln!("life-tui: {}", error);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn tui(style: TextStyle) -> Tui {
+        let origin = Node { x: -10, y: 5 };
+        Tui { game: Game::new(), style, origin, playing: false, generation: 0, speed: Duration::from_millis(200), target: 0 }
+    }
+
+    fn press(tui: &mut Tui, code: KeyCode) {
+        tui.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn clicks_either_half_block() {
+        let mut tui = tui(TextStyle::HalfBlock);
+        assert_eq!(tui.get_clicked_node(3, 2), Some(Node { x: -7, y: 1 }));
+        press(&mut tui, KeyCode::Tab);
+        assert_eq!(tui.get_target_name(), "bottom");
+        assert_eq!(tui.get_clicked_node(3, 2), Some(Node { x: -7, y: 0 }));
+        press(&mut tui, KeyCode::Tab);
+        assert_eq!(tui.get_clicked_node(3, 2), Some(Node { x: -7, y: 1 }));
+    }
+
+    #[test]
+    fn clicks_every_braille_dot() {
+        let mut tui = tui(TextStyle::Braille);
+        let mut nodes = Vec::new();
+        for _ in 0..8 {
+            nodes.push(tui.get_clicked_node(1, 1).unwrap());
+            press(&mut tui, KeyCode::Tab);
+        }
+        assert_eq!(nodes[0], Node { x: -8, y: 1 });
+        assert_eq!(nodes[1], Node { x: -7, y: 1 });
+        assert_eq!(nodes[7], Node { x: -7, y: -2 });
+        for x in -8..=-7 {
+            for y in -2..=1 {
+                assert!(nodes.contains(&Node { x, y }));
+            }
+        }
+        tui.toggle(1, 1);
+        assert!(tui.game.is_node_alive(-8, 1));
+    }
+
+    #[test]
+    fn ignores_clicks_past_the_edge_of_the_universe() {
+        let mut tui = tui(TextStyle::Braille);
+        tui.origin = Node { x: Coord::MAX, y: 0 };
+        assert_eq!(tui.get_clicked_node(1, 0), None);
+    }
+}
@@ -0,0 +1,539 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+use game_of_life::box_boundary::BoxBoundary;
+use game_of_life::census::{Census, Symmetry};
+use game_of_life::format::Format;
+use game_of_life::game::Game;
+use game_of_life::node::Node;
+use game_of_life::period::hash_cells;
+use game_of_life::render::gif::AnimationOptions;
+use game_of_life::render::svg::SvgOptions;
+use game_of_life::render::RenderOptions;
+use game_of_life::rule::Rule;
+use game_of_life::stats::{Statistics, StatsFormat};
+#[cfg(feature = "scripting")]
+use game_of_life::script::ScriptState;
+use game_of_life::topology::Topology;
+
+const USAGE: &str = "\
+usage: life <pattern file or -> [options]
+       life --script <file> [options]
+       life census [census options]
+
+options:
+  -g, --generations <n>      evolve n generations
+  -s, --until-stable <max>   evolve until the pattern dies out or repeats,
+                             at most max generations
+  -o, --output <file>        write the result to a file instead of stdout
+  -f, --format <format>      rle, cells, plaintext, life105, life106, mc, png,
+                             svg or gif, by default the output extension or rle
+  --frames <n>               animate n generations of the result as a gif,
+                             100 by default
+  --delay <ms>               show each gif frame this long, 100 by default
+  -r, --rule <rule>          use this rule instead of the pattern's
+  -t, --topology <topology>  use this Golly bounded grid instead of the pattern's
+  -x, --script <file>        run a Rhai script on the pattern, or on an empty
+                             game if there is none, before evolving it
+  --stats <file>             record the population, births, deaths, bounds and
+                             density of every generation as .csv or .json
+  -i, --inventory            count the known objects of the result, such as
+                             blocks and gliders, and print them instead of the
+                             pattern unless it goes to --output
+  -q, --quiet                don't report the population and bounds
+";
+
+const CENSUS_USAGE: &str = "\
+usage: life census [options]
+
+Evolves random 16 by 16 soups until they settle and counts the objects they
+leave behind by apgcode.
+
+options:
+  -n, --soups <n>            search n more soups, 1000 by default
+  -S, --seed <seed>          seed of the soups, 0 by default
+  -y, --symmetry <symmetry>  C1, C2, C4, D2, D4 or D8, C1 by default
+  -r, --rule <rule>          B3/S23 by default
+  -j, --threads <n>          all cores by default
+  -c, --state <file>         resume the census saved in this file and save it
+                             there after every batch of soups
+  -o, --output <file>        write the report to a file instead of stdout
+";
+
+/// How many soups are searched between saves of the census state.
+const CENSUS_BATCH: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Output {
+    Pattern(Format),
+    Png,
+    Svg,
+    Gif,
+}
+
+impl Output {
+    fn from_name(name: &str) -> Option<Output> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" => Some(Output::Png),
+            "svg" => Some(Output::Svg),
+            "gif" => Some(Output::Gif),
+            name => Format::from_name(name).map(Output::Pattern),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Run {
+    Generations(u64),
+    UntilStable(u64),
+}
+
+struct Options {
+    input: Option<String>,
+    script: Option<String>,
+    stats: Option<String>,
+    run: Run,
+    output: Option<String>,
+    format: Output,
+    /// How many generations a gif animates.
+    frames: usize,
+    frame_delay: Duration,
+    rule: Option<Rule>,
+    topology: Option<Topology>,
+    inventory: bool,
+    quiet: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut input = None;
+    let mut run = Run::Generations(0);
+    let mut output = None;
+    let mut format = None;
+    let mut frames = 100;
+    let mut frame_delay = Duration::from_millis(100);
+    let mut rule = None;
+    let mut topology = None;
+    let mut script = None;
+    let mut stats = None;
+    let mut inventory = false;
+    let mut quiet = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "-g" | "--generations" => {
+                let value = value()?;
+                run = Run::Generations(value.parse().map_err(|_| format!("invalid generations: {}", value))?);
+            }
+            "-s" | "--until-stable" => {
+                let value = value()?;
+                run = Run::UntilStable(value.parse().map_err(|_| format!("invalid generations: {}", value))?);
+            }
+            "-o" | "--output" => output = Some(value()?.clone()),
+            "-f" | "--format" => {
+                let value = value()?;
+                format = Some(Output::from_name(value).ok_or_else(|| format!("unknown format: {}", value))?);
+            }
+            "--frames" => {
+                let value = value()?;
+                frames = value.parse().ok().filter(|&frames| frames > 0).ok_or_else(|| format!("invalid frames: {}", value))?;
+            }
+            "--delay" => {
+                let value = value()?;
+                frame_delay = Duration::from_millis(value.parse().map_err(|_| format!("invalid delay: {}", value))?);
+            }
+            "-r" | "--rule" => rule = Some(value()?.parse::<Rule>().map_err(|error| error.to_string())?),
+            "-t" | "--topology" => topology = Some(value()?.parse::<Topology>().map_err(|error| error.to_string())?),
+            "-x" | "--script" => script = Some(value()?.clone()),
+            "--stats" => {
+                let value = value()?;
+                StatsFormat::from_path(Path::new(value)).map_err(|error| error.to_string())?;
+                stats = Some(value.clone());
+            }
+            "-i" | "--inventory" => inventory = true,
+            "-q" | "--quiet" => quiet = true,
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if input.is_none() && (arg == "-" || !arg.starts_with('-')) => input = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument: {}\n\n{}", arg, USAGE)),
+        }
+    }
+    let format = format
+        .or_else(|| {
+            let extension = Path::new(output.as_ref()?).extension()?.to_str()?;
+            Output::from_name(extension)
+        })
+        .unwrap_or(Output::Pattern(Format::Rle));
+    if input.is_none() && script.is_none() {
+        return Err(USAGE.to_string());
+    }
+    Ok(Options {
+        input,
+        script,
+        stats,
+        run,
+        output,
+        format,
+        frames,
+        frame_delay,
+        rule,
+        topology,
+        inventory,
+        quiet,
+    })
+}
+
+fn read_input(input: &str) -> Result<String, String> {
+    if input == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text).map_err(|error| error.to_string())?;
+        return Ok(text);
+    }
+    fs::read_to_string(input).map_err(|error| format!("{}: {}", input, error))
+}
+
+/// Evolves the game until it dies out or repeats a generation, returning how
+/// many generations that took and the period it repeats with. Generations are
+/// compared by `hash_cells`, and a matching hash is checked by evolving the
+/// first game again to the earlier generation and comparing the cells.
+fn run_until_stable(game: &mut Game, max_generations: u64, mut statistics: Option<&mut Statistics>, start: u64) -> (u64, Option<u64>) {
+    let first_game = game.clone();
+    let mut seen: HashMap<u64, u64> = HashMap::new();
+    seen.insert(hash_cells(game), 0);
+    for generation in 1..=max_generations {
+        game.evolve();
+        if let Some(statistics) = statistics.as_deref_mut() {
+            statistics.record(start + generation, game);
+        }
+        let hash = hash_cells(game);
+        match seen.get(&hash) {
+            Some(&first) if is_same_generation(&first_game, first, game) => {
+                return (generation, Some(generation - first));
+            }
+            Some(_) => {}
+            None => {
+                seen.insert(hash, generation);
+            }
+        }
+    }
+    (max_generations, None)
+}
+
+/// Whether the game is what `first_game` becomes after `generation`
+/// generations.
+fn is_same_generation(first_game: &Game, generation: u64, game: &Game) -> bool {
+    let mut earlier = first_game.clone();
+    for _ in 0..generation {
+        earlier.evolve();
+    }
+    earlier.get_sorted_cells() == game.get_sorted_cells()
+}
+
+/// Evolves the game, with hashlife on the plane unless every generation after
+/// `start` is recorded in the statistics.
+fn run_generations(mut game: Game, generations: u64, statistics: Option<&mut Statistics>, start: u64) -> Result<Game, String> {
+    match statistics {
+        Some(statistics) => {
+            for generation in 1..=generations {
+                game.evolve();
+                statistics.record(start + generation, &game);
+            }
+        }
+        None => game.step(generations).map_err(|error| error.to_string())?,
+    }
+    Ok(game)
+}
+
+fn report(game: &Game, generation: u64) {
+    match game.get_live_boundary() {
+        Some(BoxBoundary { lower, upper }) => eprintln!(
+            "generation {}: population {}, bounds ({}, {}) to ({}, {})",
+            generation, game.live_nodes.len(), lower.x, lower.y, upper.x, upper.y,
+        ),
+        None => eprintln!("generation {}: population 0", generation),
+    }
+}
+
+/// One line per kind of object, the most common first.
+fn write_inventory(game: &Game) -> Result<(), String> {
+    let mut text = String::new();
+    for (name, count) in game.get_inventory() {
+        text.push_str(&format!("{:>6} {}\n", count, name));
+    }
+    io::stdout().write_all(text.as_bytes()).map_err(|error| error.to_string())
+}
+
+/// The box holding every live node of the first `frames` generations, so that
+/// nothing moves out of the animation.
+fn get_animation_region(game: &Game, frames: usize) -> BoxBoundary {
+    let mut game = game.clone();
+    let mut region: Option<BoxBoundary> = None;
+    for frame in 0..frames {
+        if frame > 0 {
+            game.evolve();
+        }
+        if let Some(boundary) = game.get_live_boundary() {
+            region = Some(match region {
+                Some(region) => BoxBoundary {
+                    lower: Node { x: region.lower.x.min(boundary.lower.x), y: region.lower.y.min(boundary.lower.y) },
+                    upper: Node { x: region.upper.x.max(boundary.upper.x), y: region.upper.y.max(boundary.upper.y) },
+                },
+                None => boundary,
+            });
+        }
+    }
+    region.unwrap_or_else(BoxBoundary::new)
+}
+
+fn write_output(game: &Game, options: &Options) -> Result<(), String> {
+    let region = game.get_live_boundary().unwrap_or_else(BoxBoundary::new);
+    let bytes = match options.format {
+        Output::Pattern(format) => format.write(game).map_err(|error| error.to_string())?.into_bytes(),
+        Output::Png => game.to_png(&region, &RenderOptions::default()).map_err(|error| error.to_string())?,
+        Output::Svg => game.to_svg(Some(&region), &SvgOptions::default()).map_err(|error| error.to_string())?.into_bytes(),
+        Output::Gif => {
+            let region = get_animation_region(game, options.frames);
+            let animation = AnimationOptions { frame_delay: options.frame_delay, ..AnimationOptions::default() };
+            game.to_gif(&region, options.frames, &animation).map_err(|error| error.to_string())?
+        }
+    };
+    match &options.output {
+        Some(path) => fs::write(path, bytes).map_err(|error| format!("{}: {}", path, error)),
+        None => io::stdout().write_all(&bytes).map_err(|error| error.to_string()),
+    }
+}
+
+#[cfg(feature = "scripting")]
+fn run_script(path: &str, game: Game) -> Result<(Game, u64), String> {
+    let script = fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+    let state = game_of_life::script::run_script(&script, ScriptState::new(game)).map_err(|error| format!("{}: {}", path, error))?;
+    Ok((state.game, state.generation))
+}
+
+#[cfg(not(feature = "scripting"))]
+fn run_script(_path: &str, _game: Game) -> Result<(Game, u64), String> {
+    Err("scripts need life built with the scripting feature".to_string())
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let mut game = match &options.input {
+        Some(input) => {
+            let text = read_input(input)?;
+            Game::from_pattern(&text).map_err(|error| format!("{}: {}", input, error))?
+        }
+        None => Game::new(),
+    };
+    if let Some(rule) = &options.rule {
+        game.rule = rule.clone();
+    }
+    if let Some(topology) = &options.topology {
+        game.topology = topology.clone();
+    }
+    let mut generation = 0;
+    if let Some(script) = &options.script {
+        (game, generation) = run_script(script, game)?;
+    }
+    let mut statistics = options.stats.as_ref().map(|_| Statistics::new());
+    if let Some(statistics) = &mut statistics {
+        statistics.record(generation, &game);
+    }
+    generation += match options.run {
+        Run::Generations(generations) => {
+            game = run_generations(game, generations, statistics.as_mut(), generation)?;
+            generations
+        }
+        Run::UntilStable(max_generations) => {
+            let (generations, period) = run_until_stable(&mut game, max_generations, statistics.as_mut(), generation);
+            if !options.quiet {
+                match period {
+                    Some(period) => eprintln!("stable after {} generations with period {}", generations - period, period),
+                    None => eprintln!("not stable after {} generations", generations),
+                }
+            }
+            generations
+        }
+    };
+    if !options.quiet {
+        report(&game, generation);
+    }
+    if let (Some(path), Some(statistics)) = (&options.stats, &statistics) {
+        statistics.save(path).map_err(|error| error.to_string())?;
+    }
+    if options.inventory {
+        write_inventory(&game)?;
+        if options.output.is_none() {
+            return Ok(());
+        }
+    }
+    write_output(&game, &options)
+}
+
+struct CensusOptions {
+    soups: u64,
+    seed: u64,
+    symmetry: Symmetry,
+    rule: Rule,
+    threads: usize,
+    state: Option<String>,
+    output: Option<String>,
+}
+
+fn parse_census_args(args: &[String]) -> Result<CensusOptions, String> {
+    let mut options = CensusOptions {
+        soups: 1000,
+        seed: 0,
+        symmetry: Symmetry::C1,
+        rule: Rule::conway(),
+        threads: std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+        state: None,
+        output: None,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        match arg.as_str() {
+            "-n" | "--soups" => {
+                let value = value()?;
+                options.soups = value.parse().map_err(|_| format!("invalid soups: {}", value))?;
+            }
+            "-S" | "--seed" => {
+                let value = value()?;
+                options.seed = value.parse().map_err(|_| format!("invalid seed: {}", value))?;
+            }
+            "-y" | "--symmetry" => options.symmetry = value()?.parse::<Symmetry>().map_err(|error| error.to_string())?,
+            "-r" | "--rule" => options.rule = value()?.parse::<Rule>().map_err(|error| error.to_string())?,
+            "-j" | "--threads" => {
+                let value = value()?;
+                options.threads = value.parse().ok().filter(|&threads| threads > 0).ok_or_else(|| format!("invalid threads: {}", value))?;
+            }
+            "-c" | "--state" => options.state = Some(value()?.clone()),
+            "-o" | "--output" => options.output = Some(value()?.clone()),
+            "-h" | "--help" => return Err(CENSUS_USAGE.to_string()),
+            _ => return Err(format!("unexpected argument: {}\n\n{}", arg, CENSUS_USAGE)),
+        }
+    }
+    Ok(options)
+}
+
+fn run_census(options: CensusOptions) -> Result<(), String> {
+    let mut census = match &options.state {
+        Some(path) if Path::new(path).exists() => {
+            let census = Census::load(path).map_err(|error| error.to_string())?;
+            eprintln!("resuming the census of {} {} soups in {} with seed {}", census.soups, census.symmetry, census.rule, census.seed);
+            census
+        }
+        _ => Census::new(options.seed, options.symmetry, options.rule),
+    };
+    let end = census.soups.saturating_add(options.soups);
+    while census.soups < end {
+        census.search(CENSUS_BATCH.min(end - census.soups), options.threads);
+        if let Some(path) = &options.state {
+            census.save(path).map_err(|error| error.to_string())?;
+        }
+        eprintln!("{} soups, {} kinds of objects", census.soups, census.objects.len());
+    }
+    let report = census.report();
+    match &options.output {
+        Some(path) => fs::write(path, report).map_err(|error| format!("{}: {}", path, error)),
+        None => io::stdout().write_all(report.as_bytes()).map_err(|error| error.to_string()),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("census") {
+        let options = match parse_census_args(&args[1..]) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("{}", message);
+                process::exit(2);
+            }
+        };
+        if let Err(message) = run_census(options) {
+            eprintln!("life: {}", message);
+            process::exit(1);
+        }
+        return;
+    }
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    };
+    if let Err(message) = run(options) {
+        eprintln!("life: {}", message);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn parses_options() {
+        let options = parse(&["glider.rle", "-g", "100", "-o", "glider.png", "-r", "B36/S23", "-q"]).unwrap();
+        assert_eq!(options.input.as_deref(), Some("glider.rle"));
+        assert_eq!(options.run, Run::Generations(100));
+        assert_eq!(options.output.as_deref(), Some("glider.png"));
+        assert_eq!(options.format, Output::Png);
+        assert_eq!(options.rule, Some("B36/S23".parse().unwrap()));
+        assert!(options.quiet && !options.inventory);
+        let options = parse(&["-", "--until-stable", "500", "--format", "cells", "-t", "T8,8", "-i"]).unwrap();
+        assert_eq!(options.run, Run::UntilStable(500));
+        assert_eq!(options.format, Output::Pattern(Format::Cells));
+        assert_eq!(options.topology, Some("T8,8".parse().unwrap()));
+        assert!(options.inventory);
+        assert_eq!(parse(&["glider.rle"]).unwrap().format, Output::Pattern(Format::Rle));
+        assert_eq!(parse(&["glider.rle", "--stats", "growth.json"]).unwrap().stats.as_deref(), Some("growth.json"));
+        let options = parse(&["glider.rle", "-o", "glider.gif", "--frames", "20", "--delay", "50"]).unwrap();
+        assert_eq!(options.format, Output::Gif);
+        assert_eq!(options.frames, 20);
+        assert_eq!(options.frame_delay, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(parse(&[]).err(), Some(USAGE.to_string()));
+        assert_eq!(parse(&["-h"]).err(), Some(USAGE.to_string()));
+        assert_eq!(parse(&["glider.rle", "-g"]).err(), Some("-g needs a value".to_string()));
+        assert_eq!(parse(&["glider.rle", "-g", "ten"]).err(), Some("invalid generations: ten".to_string()));
+        assert_eq!(parse(&["glider.rle", "-f", "jpeg"]).err(), Some("unknown format: jpeg".to_string()));
+        assert_eq!(parse(&["glider.rle", "--frames", "0"]).err(), Some("invalid frames: 0".to_string()));
+        assert!(parse(&["glider.rle", "soup.rle"]).err().unwrap().starts_with("unexpected argument: soup.rle"));
+        assert!(parse(&["glider.rle", "-t", "T2,8"]).is_err());
+        assert!(parse(&["glider.rle", "--stats", "growth.txt"]).is_err());
+    }
+
+    #[test]
+    fn runs_until_a_generation_repeats() {
+        let mut blinker = Game::from_rle("3o!").unwrap();
+        assert_eq!(run_until_stable(&mut blinker, 10, None, 0), (2, Some(2)));
+        let mut block = Game::from_rle("o$2o!").unwrap();
+        assert_eq!(run_until_stable(&mut block, 10, None, 0), (2, Some(1)));
+        assert_eq!(block.get_sorted_cells(), vec![(0, 0), (0, 1), (1, 0), (1, 1)]);
+        let mut glider = Game::from_rle("bo$2bo$3o!").unwrap();
+        assert_eq!(run_until_stable(&mut glider, 10, None, 0), (10, None));
+        let first = Game::from_rle("3o!").unwrap();
+        let mut second = first.clone();
+        second.evolve();
+        assert!(is_same_generation(&first, 1, &second));
+        assert!(!is_same_generation(&first, 2, &second));
+    }
+
+    #[test]
+    fn frames_every_generation_of_the_animation() {
+        let glider = Game::from_rle("bo$2bo$3o!").unwrap();
+        let region = get_animation_region(&glider, 5);
+        assert_eq!(region, BoxBoundary { lower: Node { x: 0, y: -1 }, upper: Node { x: 3, y: 2 } });
+        assert_eq!(get_animation_region(&Game::new(), 5), BoxBoundary::new());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::node::Node;
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct BoxBoundary {
     pub lower: Node,
     pub upper: Node,
@@ -12,4 +13,9 @@ impl BoxBoundary {
             upper: Node { x: 0, y: 0 },
         }
     }
+
+    pub fn contains(&self, node: &Node) -> bool {
+        node.x >= self.lower.x && node.x <= self.upper.x
+            && node.y >= self.lower.y && node.y <= self.upper.y
+    }
 }
@@ -0,0 +1,151 @@
+use crate::game::Game;
+use crate::node::{Coord, Node};
+
+pub type Orientation = fn(Coord, Coord) -> (Coord, Coord);
+
+/// The eight rotations and reflections of the square, the dihedral group D8,
+/// as maps of x, y.
+pub const ORIENTATIONS: [Orientation; 8] = [
+    |x, y| (x, y),
+    |x, y| (-y, x),
+    |x, y| (-x, -y),
+    |x, y| (y, -x),
+    |x, y| (-x, y),
+    |x, y| (x, -y),
+    |x, y| (y, x),
+    |x, y| (-y, -x),
+];
+
+/// Moves the cells so that their smallest x and y are 0, and sorts them, or
+/// `None` if they span more than `Coord::MAX` in x or y so the moved cells
+/// don't fit.
+pub fn normalize(mut cells: Vec<(Coord, Coord)>) -> Option<Vec<(Coord, Coord)>> {
+    let x_min = cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+    let y_min = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+    for cell in cells.iter_mut() {
+        *cell = (cell.0.checked_sub(x_min)?, cell.1.checked_sub(y_min)?);
+    }
+    cells.sort();
+    Some(cells)
+}
+
+/// The cells in each of the `ORIENTATIONS`, normalized, or `None` if they
+/// span more than `Coord::MAX` in x or y. They're normalized first, so no
+/// orientation negates `Coord::MIN`.
+pub fn get_orientations(cells: Vec<(Coord, Coord)>) -> Option<Vec<Vec<(Coord, Coord)>>> {
+    let cells = normalize(cells)?;
+    ORIENTATIONS.iter()
+        .map(|orientation| normalize(cells.iter().map(|&(x, y)| orientation(x, y)).collect()))
+        .collect()
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+impl Game {
+    /// The live cells in whichever orientation sorts first once moved so the
+    /// smallest x and y are 0. Games that differ only by rotation, reflection
+    /// or translation have the same canonical cells. Cells spanning more than
+    /// `Coord::MAX` in x or y, only possible with cells near both ends of the
+    /// coordinates, can't be moved and are returned sorted as they are.
+    pub fn get_canonical_cells(&self) -> Vec<(Coord, Coord)> {
+        let cells = self.get_sorted_cells();
+        match get_orientations(cells.clone()) {
+            Some(orientations) => orientations.into_iter().min().unwrap_or_default(),
+            None => cells,
+        }
+    }
+
+    /// The game with its canonical cells, keeping the rule and topology.
+    pub fn to_canonical(&self) -> Game {
+        Game {
+            live_nodes: self.get_canonical_cells().into_iter().map(|(x, y)| Node { x, y }).collect(),
+            ..self.clone()
+        }
+    }
+
+    /// A 64 bit FNV-1a hash of the canonical cells, the same on every platform
+    /// and in every release, for telling patterns apart in catalogs and
+    /// search results. Each coordinate is hashed as 16 little endian bytes so
+    /// `wide-coordinates` doesn't change it.
+    // The coordinates are already i128 with `wide-coordinates`.
+    #[allow(clippy::useless_conversion)]
+    pub fn get_canonical_hash(&self) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS;
+        for (x, y) in self.get_canonical_cells() {
+            for byte in i128::from(x).to_le_bytes().into_iter().chain(i128::from(y).to_le_bytes()) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        }
+        hash
+    }
+
+    /// Whether the games have the same live cells up to rotation, reflection
+    /// and translation.
+    pub fn is_equivalent(&self, other: &Game) -> bool {
+        self.get_canonical_cells() == other.get_canonical_cells()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn transform(game: &Game, orientation: Orientation, dx: Coord, dy: Coord) -> Game {
+        Game {
+            live_nodes: game.live_nodes.iter().rev().map(|node| {
+                let (x, y) = orientation(node.x, node.y);
+                Node { x: x + dx, y: y + dy }
+            }).collect(),
+            ..Game::new()
+        }
+    }
+
+    #[test]
+    fn gives_every_orientation_and_position_the_same_canonical_form() {
+        let glider = Game::from_rle("bo$2bo$3o!").unwrap();
+        let canonical = glider.get_canonical_cells();
+        let hash = glider.get_canonical_hash();
+        for (index, orientation) in ORIENTATIONS.iter().enumerate() {
+            let moved = transform(&glider, *orientation, index as Coord * 7 - 20, 13 - index as Coord);
+            assert_eq!(moved.get_canonical_cells(), canonical);
+            assert_eq!(moved.get_canonical_hash(), hash);
+            assert!(moved.is_equivalent(&glider));
+        }
+        let mut phase = glider.clone();
+        phase.evolve();
+        assert!(!phase.is_equivalent(&glider));
+        assert_ne!(phase.get_canonical_hash(), hash);
+    }
+
+    #[test]
+    fn tells_different_patterns_apart() {
+        let block = Game::from_rle("2o$2o!").unwrap();
+        let beehive = Game::from_rle("b2o$o2bo$b2o!").unwrap();
+        assert!(!block.is_equivalent(&beehive));
+        assert_ne!(block.get_canonical_hash(), beehive.get_canonical_hash());
+        assert_eq!(block.to_canonical().live_nodes, vec![Node { x: 0, y: 0 }, Node { x: 0, y: 1 }, Node { x: 1, y: 0 }, Node { x: 1, y: 1 }]);
+    }
+
+    #[test]
+    fn handles_cells_at_the_ends_of_the_coordinates() {
+        let mut corner = Game::new();
+        corner.live_nodes = vec![Node { x: Coord::MIN, y: Coord::MIN }, Node { x: Coord::MIN + 1, y: Coord::MIN }];
+        let mut moved = Game::new();
+        moved.live_nodes = vec![Node { x: Coord::MAX, y: Coord::MAX }, Node { x: Coord::MAX, y: Coord::MAX - 1 }];
+        assert_eq!(corner.get_canonical_cells(), vec![(0, 0), (0, 1)]);
+        assert!(corner.is_equivalent(&moved));
+        let mut spread = Game::new();
+        spread.live_nodes = vec![Node { x: Coord::MAX, y: 0 }, Node { x: Coord::MIN, y: 0 }];
+        assert_eq!(spread.get_canonical_cells(), vec![(Coord::MIN, 0), (Coord::MAX, 0)]);
+        assert_eq!(normalize(vec![(Coord::MIN, 0), (Coord::MAX, 0)]), None);
+        assert_eq!(normalize(vec![(-1, Coord::MAX), (Coord::MAX - 1, 0)]), Some(vec![(0, Coord::MAX), (Coord::MAX, 0)]));
+    }
+
+    #[test]
+    fn keeps_the_hash_stable() {
+        assert_eq!(Game::new().get_canonical_hash(), FNV_OFFSET_BASIS);
+        assert_eq!(Game::from_rle("o!").unwrap().get_canonical_hash(), 0x0c8210784d8af5a5);
+    }
+}
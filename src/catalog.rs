@@ -0,0 +1,158 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+use crate::game::Game;
+use crate::node::Coord;
+use crate::object::Object;
+use crate::period::find_translation_phases;
+use crate::rule::Rule;
+
+/// Common B3/S23 objects by name, as RLE in any one phase.
+const CATALOG: [(&str, &str); 25] = [
+    ("block", "2o$2o!"),
+    ("beehive", "b2o$o2bo$b2o!"),
+    ("loaf", "b2o$o2bo$bobo$2bo!"),
+    ("boat", "2o$obo$bo!"),
+    ("ship", "2o$obo$b2o!"),
+    ("tub", "bo$obo$bo!"),
+    ("pond", "b2o$o2bo$o2bo$b2o!"),
+    ("barge", "bo$obo$bobo$2bo!"),
+    ("long boat", "2o$obo$bobo$2bo!"),
+    ("long barge", "bo$obo$bobo$2bobo$3bo!"),
+    ("mango", "b2o$o2bo$bo2bo$2b2o!"),
+    ("snake", "2obo$ob2o!"),
+    ("aircraft carrier", "2o$o2bo$2b2o!"),
+    ("eater 1", "2o$obo$2bo$2b2o!"),
+    ("integral sign", "2o$obo$2bo$2bobo$3b2o!"),
+    ("blinker", "3o!"),
+    ("toad", "b3o$3o!"),
+    ("beacon", "2o$2o$2b2o$2b2o!"),
+    ("clock", "2bo$obo$bobo$bo!"),
+    ("pulsar", "2b3o3b3o2$o4bobo4bo$o4bobo4bo$o4bobo4bo$2b3o3b3o2$2b3o3b3o$o4bobo4bo$o4bobo4bo$o4bobo4bo2$2b3o3b3o!"),
+    ("pentadecathlon", "2bo4bo$2ob4ob2o$2bo4bo!"),
+    ("glider", "bo$2bo$3o!"),
+    ("lightweight spaceship", "bo2bo$o$o3bo$4o!"),
+    ("middleweight spaceship", "3bo$bo3bo$o$o4bo$5o!"),
+    ("heavyweight spaceship", "3b2o$bo4bo$o$o5bo$6o!"),
+];
+
+/// The name objects that aren't in the catalog are counted under.
+pub const UNKNOWN: &str = "unknown";
+
+/// The canonical cells of every phase of every catalog object.
+fn get_index() -> &'static HashMap<Vec<(Coord, Coord)>, &'static str> {
+    static INDEX: OnceLock<HashMap<Vec<(Coord, Coord)>, &'static str>> = OnceLock::new();
+    INDEX.get_or_init(|| {
+        let mut index = HashMap::new();
+        for (name, rle) in CATALOG {
+            let game = Game::from_rle(rle).expect("catalog patterns are valid RLE");
+            let (phases, _, _) = find_translation_phases(&game, 30).expect("catalog patterns repeat");
+            for phase in phases {
+                index.insert(phase.get_canonical_cells(), name);
+            }
+        }
+        index
+    })
+}
+
+/// The catalog name of an object in B3/S23, in any phase, orientation and
+/// position.
+pub fn identify(game: &Game) -> Option<&'static str> {
+    if game.rule != Rule::conway() {
+        return None;
+    }
+    get_index().get(&game.get_canonical_cells()).copied()
+}
+
+/// An object of a game and its catalog name, if it has one.
+pub struct RecognizedObject {
+    pub name: Option<&'static str>,
+    pub object: Object,
+}
+
+impl RecognizedObject {
+    /// Where a label with the object's name starts, in nodes: one node right
+    /// of the object and halfway up it. `None` for unknown objects.
+    pub fn get_label_position(&self) -> Option<(f64, f64)> {
+        self.name?;
+        let bounds = self.object.game.get_live_boundary()?;
+        let offset = &self.object.offset;
+        let x = offset.x as f64 + bounds.upper.x as f64 + 1.;
+        let y = offset.y as f64 + (bounds.lower.y as f64 + bounds.upper.y as f64) / 2.;
+        Some((x, y))
+    }
+}
+
+impl Game {
+    /// Splits the game into objects, as `split_objects(2)` does, and looks
+    /// each of them up in the catalog.
+    pub fn recognize_objects(&self) -> Vec<RecognizedObject> {
+        self.split_objects(2)
+            .into_iter()
+            .map(|object| RecognizedObject { name: identify(&object.game), object })
+            .collect()
+    }
+
+    /// How many of each known object the game has, the most common first,
+    /// with the rest counted as `UNKNOWN`.
+    pub fn get_inventory(&self) -> Vec<(&'static str, usize)> {
+        let mut counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+        for recognized in self.recognize_objects() {
+            *counts.entry(recognized.name.unwrap_or(UNKNOWN)).or_insert(0) += 1;
+        }
+        let mut inventory: Vec<(&'static str, usize)> = counts.into_iter().collect();
+        inventory.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        inventory
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::node::Node;
+    use crate::period::find_translation_period;
+
+    #[test]
+    fn repeats_every_catalog_object() {
+        for (name, rle) in CATALOG {
+            let game = Game::from_rle(rle).unwrap();
+            assert!(find_translation_period(&game, 30).is_some(), "{}", name);
+            assert_eq!(identify(&game), Some(name));
+        }
+    }
+
+    #[test]
+    fn identifies_objects_in_any_phase_and_orientation() {
+        let mut glider = Game::from_rle("bo$2bo$3o!").unwrap();
+        glider.evolve();
+        glider.live_nodes = glider.live_nodes.iter().map(|node| Node { x: -node.y + 40, y: node.x }).collect();
+        assert_eq!(identify(&glider), Some("glider"));
+        let mut toad = Game::from_rle("b3o$3o!").unwrap();
+        toad.evolve();
+        assert_eq!(identify(&toad), Some("toad"));
+        assert_eq!(identify(&Game::from_rle("o$2o!").unwrap()), None);
+        assert_eq!(identify(&Game::with_rule("B36/S23".parse().unwrap())), None);
+    }
+
+    #[test]
+    fn counts_known_objects() {
+        let mut game = Game::from_rle("2o5b3o$2o!").unwrap();
+        for (dx, rle) in [(20, "2o$2o!"), (30, "bo$2bo$3o!"), (40, "o$2o!")] {
+            for node in Game::from_rle(rle).unwrap().live_nodes {
+                game.live_nodes.push(Node { x: node.x + dx, y: node.y });
+            }
+        }
+        assert_eq!(game.get_inventory(), vec![("block", 2), ("blinker", 1), ("glider", 1), (UNKNOWN, 1)]);
+        let recognized = game.recognize_objects();
+        let glider = recognized.iter().find(|recognized| recognized.name == Some("glider")).unwrap();
+        assert_eq!((glider.object.offset.x, glider.object.offset.y), (30, 0));
+    }
+
+    #[test]
+    fn places_labels_right_of_known_objects() {
+        let game = Game::from_rle("2o8b3o$2o!").unwrap();
+        let positions: Vec<_> = game.recognize_objects().iter().map(|recognized| recognized.get_label_position()).collect();
+        assert_eq!(positions, vec![Some((2., 0.5)), Some((13., 1.))]);
+        let unknown = Game::from_rle("o$2o!").unwrap().recognize_objects();
+        assert_eq!(unknown[0].get_label_position(), None);
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use serde::{Deserialize, Serialize};
+use crate::game::Game;
+use crate::node::{Coord, Node};
+use crate::quadtree::QuadTree;
+use crate::rule::Rule;
+
+/// The side of a soup, in nodes.
+pub const SOUP_SIZE: Coord = 16;
+/// The longest period an object is classified with.
+const MAX_PERIOD: usize = 60;
+/// Soups whose population isn't periodic after this many generations are
+/// counted as pathological.
+const MAX_GENERATIONS: u64 = 20000;
+/// How many generations the population has to repeat for before a soup is
+/// considered stable.
+const STABLE_WINDOW: usize = 4 * MAX_PERIOD;
+/// The name soups that never stabilize and objects that can't be classified
+/// are counted under.
+pub const PATHOLOGICAL: &str = "PATHOLOGICAL";
+
+/// SplitMix64, a small seedable generator, so soups are the same on every
+/// platform and for every thread count.
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Maps a node within a soup to another.
+type SoupMap = fn(Coord, Coord) -> (Coord, Coord);
+
+/// The symmetry of a soup, after the apgsearch names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Symmetry {
+    /// No symmetry.
+    C1,
+    /// Unchanged by a half turn.
+    C2,
+    /// Unchanged by a quarter turn.
+    C4,
+    /// Mirrored left to right.
+    D2,
+    /// Mirrored left to right and top to bottom.
+    D4,
+    /// Unchanged by every rotation and reflection.
+    D8,
+}
+
+impl Symmetry {
+    /// The maps of x, y within the soup that leave it unchanged.
+    fn get_maps(&self) -> Vec<SoupMap> {
+        const LAST: Coord = SOUP_SIZE - 1;
+        let identity: SoupMap = |x, y| (x, y);
+        let half_turn: SoupMap = |x, y| (LAST - x, LAST - y);
+        let quarter_turn: SoupMap = |x, y| (LAST - y, x);
+        let three_quarter_turn: SoupMap = |x, y| (y, LAST - x);
+        let mirror_x: SoupMap = |x, y| (LAST - x, y);
+        let mirror_y: SoupMap = |x, y| (x, LAST - y);
+        let diagonal: SoupMap = |x, y| (y, x);
+        let anti_diagonal: SoupMap = |x, y| (LAST - y, LAST - x);
+        match self {
+            Symmetry::C1 => vec![identity],
+            Symmetry::C2 => vec![identity, half_turn],
+            Symmetry::C4 => vec![identity, quarter_turn, half_turn, three_quarter_turn],
+            Symmetry::D2 => vec![identity, mirror_x],
+            Symmetry::D4 => vec![identity, mirror_x, mirror_y, half_turn],
+            Symmetry::D8 => vec![
+                identity, quarter_turn, half_turn, three_quarter_turn,
+                mirror_x, mirror_y, diagonal, anti_diagonal,
+            ],
+        }
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseSymmetryError(String);
+
+impl fmt::Display for ParseSymmetryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid symmetry: {}, expected C1, C2, C4, D2, D4 or D8", self.0)
+    }
+}
+
+impl std::error::Error for ParseSymmetryError {}
+
+impl FromStr for Symmetry {
+    type Err = ParseSymmetryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "C1" => Ok(Symmetry::C1),
+            "C2" => Ok(Symmetry::C2),
+            "C4" => Ok(Symmetry::C4),
+            "D2" => Ok(Symmetry::D2),
+            "D4" => Ok(Symmetry::D4),
+            "D8" => Ok(Symmetry::D8),
+            _ => Err(ParseSymmetryError(s.to_string())),
+        }
+    }
+}
+
+/// The soup with this index in the search seeded with `seed`: a 16x16 square
+/// with its bottom left corner at the origin, each node alive with even odds
+/// and the rest following from the symmetry.
+pub fn generate_soup(seed: u64, index: u64, symmetry: Symmetry) -> Game {
+    let mut random = SplitMix64::new(SplitMix64::new(seed).next_u64() ^ index);
+    let maps = symmetry.get_maps();
+    let mut alive: HashMap<(Coord, Coord), bool> = HashMap::new();
+    let mut bits = 0;
+    let mut remaining = 0;
+    let mut game = Game::new();
+    for y in 0..SOUP_SIZE {
+        for x in 0..SOUP_SIZE {
+            let orbit = maps.iter().map(|map| map(x, y)).min().unwrap_or((x, y));
+            let node_alive = match alive.get(&orbit) {
+                Some(&node_alive) => node_alive,
+                None => {
+                    if remaining == 0 {
+                        bits = random.next_u64();
+                        remaining = 64;
+                    }
+                    let node_alive = bits & 1 == 1;
+                    bits >>= 1;
+                    remaining -= 1;
+                    alive.insert(orbit, node_alive);
+                    node_alive
+                }
+            };
+            if node_alive {
+                game.live_nodes.push(Node { x, y });
+            }
+        }
+    }
+    game
+}
+
+/// Evolves the soup until its population has been periodic for a while,
+/// returning the stable pattern, or `None` if it never settles or grows too
+/// far for the quadtree.
+fn stabilize(soup: &Game) -> Option<Game> {
+    let mut tree = QuadTree::from_game(soup).ok()?;
+    let mut populations = vec![tree.get_population()];
+    for generation in 1..=MAX_GENERATIONS as usize {
+        tree.step(1).ok()?;
+        populations.push(tree.get_population());
+        if generation < STABLE_WINDOW + MAX_PERIOD || generation % MAX_PERIOD != 0 {
+            continue;
+        }
+        let recent = &populations[generation - STABLE_WINDOW - MAX_PERIOD..];
+        let periodic = (1..=MAX_PERIOD).any(|period| {
+            (period..recent.len()).all(|index| recent[index] == recent[index - period])
+        });
+        if periodic {
+            return Some(tree.to_game());
+        }
+    }
+    None
+}
+
+/// The apgcodes of the objects a soup settles into.
+pub fn census_soup(soup: &Game) -> Vec<String> {
+    match stabilize(soup) {
+        Some(stable) => stable.split_objects(2)
+            .iter()
+            .map(|object| object.game.apgcode(MAX_PERIOD).unwrap_or_else(|| PATHOLOGICAL.to_string()))
+            .collect(),
+        None => vec![PATHOLOGICAL.to_string()],
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CensusError(String);
+
+impl fmt::Display for CensusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "census error: {}", self.0)
+    }
+}
+
+impl std::error::Error for CensusError {}
+
+/// The tally of a soup search. Soups are numbered from 0, so a census saved
+/// after some soups resumes with the next one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Census {
+    pub seed: u64,
+    pub symmetry: Symmetry,
+    pub rule: Rule,
+    /// How many soups have been searched.
+    pub soups: u64,
+    /// How many of each object, by apgcode, the soups settled into.
+    pub objects: BTreeMap<String, u64>,
+}
+
+impl Census {
+    pub fn new(seed: u64, symmetry: Symmetry, rule: Rule) -> Self {
+        Census { seed, symmetry, rule, soups: 0, objects: BTreeMap::new() }
+    }
+
+    /// Searches the next `soups` soups on `threads` threads.
+    pub fn search(&mut self, soups: u64, threads: usize) {
+        let start = self.soups;
+        let next = AtomicU64::new(start);
+        let end = start.saturating_add(soups);
+        let totals = Mutex::new(BTreeMap::new());
+        thread::scope(|scope| {
+            for _ in 0..threads.max(1) {
+                scope.spawn(|| {
+                    let mut objects: BTreeMap<String, u64> = BTreeMap::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= end {
+                            break;
+                        }
+                        let mut soup = generate_soup(self.seed, index, self.symmetry);
+                        soup.rule = self.rule.clone();
+                        for apgcode in census_soup(&soup) {
+                            *objects.entry(apgcode).or_insert(0) += 1;
+                        }
+                    }
+                    let mut totals = totals.lock().unwrap();
+                    for (apgcode, count) in objects {
+                        *totals.entry(apgcode).or_insert(0) += count;
+                    }
+                });
+            }
+        });
+        for (apgcode, count) in totals.into_inner().unwrap() {
+            *self.objects.entry(apgcode).or_insert(0) += count;
+        }
+        self.soups = end;
+    }
+
+    /// The objects found, the most common first.
+    pub fn report(&self) -> String {
+        let mut objects: Vec<(&String, &u64)> = self.objects.iter().collect();
+        objects.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        let total: u64 = self.objects.values().sum();
+        let mut report = format!(
+            "# Census of {} {} soups in {} with seed {}\n# {} objects\n",
+            self.soups, self.symmetry, self.rule, self.seed, total,
+        );
+        for (apgcode, count) in objects {
+            report.push_str(&format!("{} {}\n", apgcode, count));
+        }
+        report
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CensusError> {
+        let path = path.as_ref();
+        let text = serde_json::to_string_pretty(self).map_err(|error| CensusError(error.to_string()))?;
+        fs::write(path, text).map_err(|error| CensusError(format!("{}: {}", path.display(), error)))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Census, CensusError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|error| CensusError(format!("{}: {}", path.display(), error)))?;
+        serde_json::from_str(&text).map_err(|error| CensusError(format!("{}: {}", path.display(), error)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generates_the_same_soup_for_the_same_seed_and_index() {
+        let soup = generate_soup(7, 3, Symmetry::C1);
+        assert_eq!(soup.get_sorted_cells(), generate_soup(7, 3, Symmetry::C1).get_sorted_cells());
+        assert_ne!(soup.get_sorted_cells(), generate_soup(7, 4, Symmetry::C1).get_sorted_cells());
+        assert_ne!(soup.get_sorted_cells(), generate_soup(8, 3, Symmetry::C1).get_sorted_cells());
+        assert!(soup.live_nodes.len() > 64 && soup.live_nodes.len() < 192);
+        assert!(soup.live_nodes.iter().all(|node| (0..SOUP_SIZE).contains(&node.x) && (0..SOUP_SIZE).contains(&node.y)));
+    }
+
+    #[test]
+    fn generates_symmetric_soups() {
+        for symmetry in [Symmetry::C2, Symmetry::C4, Symmetry::D2, Symmetry::D4, Symmetry::D8] {
+            let soup = generate_soup(1, 0, symmetry);
+            for map in symmetry.get_maps() {
+                let mapped = Game {
+                    live_nodes: soup.live_nodes.iter().map(|node| {
+                        let (x, y) = map(node.x, node.y);
+                        Node { x, y }
+                    }).collect(),
+                    ..Game::new()
+                };
+                assert_eq!(mapped.get_sorted_cells(), soup.get_sorted_cells(), "{}", symmetry);
+            }
+        }
+        assert_eq!("d8".parse::<Symmetry>().unwrap(), Symmetry::D8);
+        assert!("C3".parse::<Symmetry>().is_err());
+    }
+
+    #[test]
+    fn classifies_the_objects_a_pattern_settles_into() {
+        let mut soup = Game::from_rle("2o5b3o$2o!").unwrap();
+        for node in Game::from_rle("bo$2bo$3o!").unwrap().live_nodes {
+            soup.live_nodes.push(Node { x: node.x + 20, y: node.y - 20 });
+        }
+        let mut objects = census_soup(&soup);
+        objects.sort();
+        assert_eq!(objects, vec!["xp2_7", "xq4_153", "xs4_33"]);
+        assert_eq!(census_soup(&Game::new()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn counts_the_same_objects_on_any_number_of_threads_and_when_resumed() {
+        let mut single = Census::new(42, Symmetry::C1, Rule::conway());
+        single.search(6, 1);
+        let mut parallel = Census::new(42, Symmetry::C1, Rule::conway());
+        parallel.search(6, 4);
+        assert_eq!(parallel, single);
+        let mut resumed = Census::new(42, Symmetry::C1, Rule::conway());
+        resumed.search(2, 2);
+        let path = std::env::temp_dir().join(format!("game_of_life_census_{}.json", std::process::id()));
+        resumed.save(&path).unwrap();
+        let mut resumed = Census::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        resumed.search(4, 2);
+        assert_eq!(resumed, single);
+        assert_eq!(single.soups, 6);
+        assert!(single.objects.contains_key("xs4_33"));
+        assert!(single.report().starts_with("# Census of 6 C1 soups in B3/S23 with seed 42\n"));
+    }
+}
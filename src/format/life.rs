@@ -0,0 +1,276 @@
+use std::cmp::Reverse;
+use crate::format::{ParseError, WriteError};
+use crate::game::Game;
+use crate::node::{Coord, Node};
+use crate::rule::Rule;
+
+/// The widest line the Life 1.05 format allows.
+const BLOCK_WIDTH: Coord = 80;
+
+fn parse_pair(text: &str, line_number: usize) -> Result<(Coord, Coord), ParseError> {
+    let mut values = text.split_whitespace().map(|value| value.parse::<Coord>());
+    match (values.next(), values.next(), values.next()) {
+        (Some(Ok(x)), Some(Ok(y)), None) => Ok((x, y)),
+        _ => Err(ParseError::new(line_number, format!("expected `x y`, found `{}`", text.trim()))),
+    }
+}
+
+/// The node at x, y in file coordinates, y pointing down, if the game can hold it.
+fn get_node(x: Option<Coord>, y: Option<Coord>, line_number: usize) -> Result<Node, ParseError> {
+    match (x, y.and_then(Coord::checked_neg)) {
+        (Some(x), Some(y)) => Ok(Node { x, y }),
+        _ => Err(ParseError::new(line_number, "node is out of range")),
+    }
+}
+
+/// What the lines following a `#P`, `#A` or `#R` line describe.
+enum Block {
+    Picture { x: Coord, y: Coord, row: Coord },
+    Coordinates { x: Coord, y: Coord },
+}
+
+impl Game {
+    /// Reads the Life 1.05 format and the Xlife format it grew out of: `#P x y`
+    /// blocks of `*` and `.` rows placed at x, y, Xlife's `#A` absolute and
+    /// `#R x y` relative coordinate lists, and a `#N` or `#R 23/3` rule. File
+    /// coordinates point y down, the game's y up.
+    pub fn from_life105(text: &str) -> Result<Game, ParseError> {
+        let mut game = Game::new();
+        let mut block: Option<Block> = None;
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(directive) = line.strip_prefix('#') {
+                let (kind, rest) = directive.split_at(directive.chars().next().map_or(0, char::len_utf8));
+                match kind {
+                    "P" => {
+                        let (x, y) = parse_pair(rest, line_number)?;
+                        block = Some(Block::Picture { x, y, row: 0 });
+                    }
+                    "A" => block = Some(Block::Coordinates { x: 0, y: 0 }),
+                    "R" if rest.contains('/') => {
+                        game.rule = rest.trim().parse::<Rule>()
+                            .map_err(|error| ParseError::new(line_number, error.to_string()))?;
+                    }
+                    "R" => {
+                        let (x, y) = if rest.trim().is_empty() { (0, 0) } else { parse_pair(rest, line_number)? };
+                        block = Some(Block::Coordinates { x, y });
+                    }
+                    "N" => game.rule = Rule::conway(),
+                    _ => {}
+                }
+                continue;
+            }
+            let block = block.get_or_insert(Block::Picture { x: 0, y: 0, row: 0 });
+            match block {
+                Block::Picture { x, y, row } => {
+                    for (column, cell) in line.chars().enumerate() {
+                        match cell {
+                            '*' | 'O' => {
+                                let column = Coord::try_from(column).ok();
+                                let node_x = column.and_then(|column| x.checked_add(column));
+                                game.live_nodes.push(get_node(node_x, y.checked_add(*row), line_number)?);
+                            }
+                            '.' => {}
+                            _ => return Err(ParseError::new(line_number, format!("unexpected `{}`", cell))),
+                        }
+                    }
+                    *row += 1;
+                }
+                Block::Coordinates { x, y } => {
+                    let (dx, dy) = parse_pair(line, line_number)?;
+                    game.live_nodes.push(get_node(x.checked_add(dx), y.checked_add(dy), line_number)?);
+                }
+            }
+        }
+        Ok(game)
+    }
+
+    /// Writes the Life 1.05 format, splitting the pattern into `#P` blocks no
+    /// wider than 80 columns. A block ends at a gap of more than 80 empty
+    /// rows, so nodes far apart don't fill the file with empty lines.
+    pub fn to_life105(&self) -> Result<String, WriteError> {
+        let mut text = "#Life 1.05\n".to_string();
+        if self.rule == Rule::conway() {
+            text.push_str("#N\n");
+        } else {
+            text.push_str(&format!("#R {}\n", self.rule.to_survival_birth_string()));
+        }
+        let left = match self.get_live_boundary() {
+            Some(boundary) => boundary.lower.x,
+            None => return Ok(text),
+        };
+        let column_of = |x: Coord| x.abs_diff(left) / BLOCK_WIDTH.unsigned_abs();
+        let mut cells = self.get_sorted_cells();
+        cells.sort_by_key(|&(x, y)| (column_of(x), Reverse(y), x));
+        // The block column, its left x and the last row written.
+        let mut block: Option<(_, Coord, Coord)> = None;
+        for (x, y) in cells {
+            let column = column_of(x);
+            let (block_left, row, start) = match block {
+                Some((block_column, block_left, row))
+                    if block_column == column && row.abs_diff(y) <= BLOCK_WIDTH.unsigned_abs() => (block_left, row, row != y),
+                _ => {
+                    let block_left = x - (x.abs_diff(left) % BLOCK_WIDTH.unsigned_abs()) as Coord;
+                    let top = y.checked_neg()
+                        .ok_or_else(|| WriteError::new(format!("y position {} is out of range", y)))?;
+                    if block.is_some() {
+                        text.push('\n');
+                    }
+                    text.push_str(&format!("#P {} {}", block_left, top));
+                    (block_left, y, true)
+                }
+            };
+            if start {
+                for _ in 1..row.abs_diff(y) {
+                    text.push_str("\n.");
+                }
+                text.push('\n');
+                text.push_str(&".".repeat(x.abs_diff(block_left) as usize));
+            } else {
+                let written = text.len() - text.rfind('\n').map_or(0, |newline| newline + 1);
+                text.push_str(&".".repeat(x.abs_diff(block_left) as usize - written));
+            }
+            text.push('*');
+            block = Some((column, block_left, y));
+        }
+        text.push('\n');
+        Ok(text)
+    }
+
+    /// Reads the Life 1.06 format, a `#Life 1.06` header followed by one
+    /// `x y` line per live node. File coordinates point y down, the game's y up.
+    pub fn from_life106(text: &str) -> Result<Game, ParseError> {
+        let mut game = Game::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (x, y) = parse_pair(line, index + 1)?;
+            game.live_nodes.push(get_node(Some(x), Some(y), index + 1)?);
+        }
+        Ok(game)
+    }
+
+    /// Writes the Life 1.06 format, failing on a node at `Coord::MIN` y, which
+    /// the file's downward y can't hold.
+    pub fn to_life106(&self) -> Result<String, WriteError> {
+        let mut text = "#Life 1.06\n".to_string();
+        for node in &self.live_nodes {
+            let y = node.y.checked_neg()
+                .ok_or_else(|| WriteError::new(format!("y position {} is out of range", node.y)))?;
+            text.push_str(&format!("{} {}\n", node.x, y));
+        }
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::assert_same_nodes;
+
+    fn glider() -> Vec<Node> {
+        vec![
+            Node { x: 0, y: 1 },
+            Node { x: 1, y: 0 },
+            Node { x: -1, y: -1 },
+            Node { x: 0, y: -1 },
+            Node { x: 1, y: -1 },
+        ]
+    }
+
+    #[test]
+    fn reads_life_106_coordinates() {
+        let game = Game::from_life106("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n").unwrap();
+        assert_same_nodes(&game.live_nodes, &glider());
+        assert_eq!(Game::from_life106("#Life 1.06\n0 -1\n1\n").err().unwrap().line, 3);
+    }
+
+    #[test]
+    fn round_trips_life_106() {
+        let game = Game { live_nodes: glider(), ..Game::new() };
+        let copy = Game::from_life106(&game.to_life106().unwrap()).unwrap();
+        assert_same_nodes(&copy.live_nodes, &game.live_nodes);
+    }
+
+    #[test]
+    fn reads_life_105_blocks() {
+        let game = Game::from_life105("\
+#Life 1.05
+#D Two gliders
+#N
+#P -1 -1
+.*
+..*
+***
+#P 10 20
+.*
+..*
+***
+").unwrap();
+        assert_eq!(game.live_nodes.len(), 10);
+        assert!(game.live_nodes.contains(&Node { x: 0, y: 1 }));
+        assert!(game.live_nodes.contains(&Node { x: 11, y: -20 }));
+        assert!(game.live_nodes.contains(&Node { x: 12, y: -22 }));
+        assert_eq!(game.rule, Rule::conway());
+    }
+
+    #[test]
+    fn reads_life_105_rule() {
+        let game = Game::from_life105("#Life 1.05\n#R 23/36\n#P 0 0\n*\n").unwrap();
+        assert_eq!(game.rule, "B36/S23".parse().unwrap());
+        assert!(Game::from_life105("#Life 1.05\n#R 23/x\n").is_err());
+    }
+
+    #[test]
+    fn reads_xlife_coordinate_lists() {
+        let game = Game::from_life105("#A\n0 -1\n1 0\n#R 10 10\n-1 1\n0 1\n1 1\n").unwrap();
+        assert_same_nodes(&game.live_nodes, &[
+            Node { x: 0, y: 1 },
+            Node { x: 1, y: 0 },
+            Node { x: 9, y: -11 },
+            Node { x: 10, y: -11 },
+            Node { x: 11, y: -11 },
+        ]);
+    }
+
+    #[test]
+    fn round_trips_life_105() {
+        let mut game = Game { live_nodes: glider(), ..Game::new() };
+        game.live_nodes.push(Node { x: 200, y: 5 });
+        game.rule = "B36/S23".parse().unwrap();
+        let text = game.to_life105().unwrap();
+        assert!(text.starts_with("#Life 1.05\n#R 23/36\n#P -1 -1\n.*\n..*\n***\n"));
+        assert!(text.lines().all(|line| line.len() <= BLOCK_WIDTH as usize));
+        let copy = Game::from_life105(&text).unwrap();
+        assert_same_nodes(&copy.live_nodes, &game.live_nodes);
+        assert_eq!(copy.rule, game.rule);
+    }
+
+    #[test]
+    fn rejects_nodes_out_of_range() {
+        let error = Game::from_life106(&format!("#Life 1.06\n0 {}\n", Coord::MIN)).err().unwrap();
+        assert_eq!(error.line, 2);
+        assert_eq!(Game::from_life105(&format!("#P {} 0\n.*\n", Coord::MAX)).err().unwrap().line, 2);
+        assert_eq!(Game::from_life105(&format!("#R 0 {}\n0 1\n", Coord::MAX)).err().unwrap().line, 2);
+        let game = Game { live_nodes: vec![Node { x: 0, y: Coord::MIN }], ..Game::new() };
+        assert!(game.to_life106().is_err());
+        assert!(game.to_life105().is_err());
+    }
+
+    #[test]
+    fn writes_nodes_far_apart_in_separate_blocks() {
+        let game = Game {
+            live_nodes: vec![Node { x: Coord::MIN, y: Coord::MAX }, Node { x: Coord::MAX, y: -Coord::MAX }, Node { x: Coord::MIN + 2, y: 0 }],
+            ..Game::new()
+        };
+        let text = game.to_life105().unwrap();
+        assert_eq!(text.lines().filter(|line| line.starts_with("#P")).count(), 3);
+        assert_same_nodes(&Game::from_life105(&text).unwrap().live_nodes, &game.live_nodes);
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use crate::format::{ParseError, WriteError};
+use crate::game::Game;
+use crate::quadtree::{Quad, QuadId, QuadTree, MAX_LEVEL};
+use crate::rule::Rule;
+use crate::topology::Topology;
+
+/// Macrocell leaves are 8x8 squares, level 3 quads.
+const LEAF_LEVEL: u32 = 3;
+const LEAF_SIZE: usize = 8;
+
+fn join_leaf(tree: &mut QuadTree, cells: &[[bool; LEAF_SIZE]; LEAF_SIZE], x: usize, y: usize, size: usize) -> QuadId {
+    if size == 1 {
+        return tree.get_leaf(cells[y][x]);
+    }
+    let half = size / 2;
+    let nw = join_leaf(tree, cells, x, y, half);
+    let ne = join_leaf(tree, cells, x + half, y, half);
+    let sw = join_leaf(tree, cells, x, y + half, half);
+    let se = join_leaf(tree, cells, x + half, y + half, half);
+    tree.join(nw, ne, sw, se)
+}
+
+fn fill_leaf(tree: &QuadTree, id: QuadId, x: usize, y: usize, cells: &mut [[bool; LEAF_SIZE]; LEAF_SIZE]) {
+    if tree.get_population_of(id) == 0 {
+        return;
+    }
+    match tree.get_quad(id) {
+        Quad::Leaf(alive) => cells[y][x] = alive,
+        Quad::Branch { level, nw, ne, sw, se } => {
+            let half = 1 << (level - 1);
+            fill_leaf(tree, nw, x, y, cells);
+            fill_leaf(tree, ne, x + half, y, cells);
+            fill_leaf(tree, sw, x, y + half, cells);
+            fill_leaf(tree, se, x + half, y + half, cells);
+        }
+    }
+}
+
+fn parse_leaf(line: &str, line_number: usize) -> Result<[[bool; LEAF_SIZE]; LEAF_SIZE], ParseError> {
+    let mut cells = [[false; LEAF_SIZE]; LEAF_SIZE];
+    let (mut x, mut y) = (0, 0);
+    for cell in line.chars() {
+        match cell {
+            '.' | '*' if x < LEAF_SIZE && y < LEAF_SIZE => {
+                cells[y][x] = cell == '*';
+                x += 1;
+            }
+            '$' if y < LEAF_SIZE => {
+                x = 0;
+                y += 1;
+            }
+            '.' | '*' | '$' => return Err(ParseError::new(line_number, "leaf is larger than 8x8")),
+            _ => return Err(ParseError::new(line_number, format!("unexpected `{}`", cell))),
+        }
+    }
+    Ok(cells)
+}
+
+impl QuadTree {
+    /// Reads a Macrocell pattern straight into the quadtree, keeping the
+    /// sharing of identical quads the file describes. The root quad is
+    /// centered on the origin.
+    pub fn from_macrocell(text: &str) -> Result<QuadTree, ParseError> {
+        let mut tree = QuadTree::new();
+        let mut ids: Vec<QuadId> = Vec::new();
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.starts_with("[M2]") => {}
+            _ => return Err(ParseError::new(1, "expected `[M2]` header")),
+        }
+        for (index, line) in lines {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some(rule) = comment.strip_prefix('R') {
+                    let (rule, topology) = rule.trim().split_once(':').unwrap_or((rule.trim(), ""));
+                    let rule = rule.parse::<Rule>()
+                        .map_err(|error| ParseError::new(line_number, error.to_string()))?;
+                    let topology = topology.parse::<Topology>()
+                        .map_err(|error| ParseError::new(line_number, error.to_string()))?;
+                    tree.set_rule(rule);
+                    tree.set_topology(topology);
+                } else if let Some(generation) = comment.strip_prefix('G') {
+                    let generation = generation.trim().parse()
+                        .map_err(|_| ParseError::new(line_number, "invalid generation"))?;
+                    tree.set_generation(generation);
+                }
+                continue;
+            }
+            if line.starts_with(['.', '*', '$']) {
+                let cells = parse_leaf(line, line_number)?;
+                ids.push(join_leaf(&mut tree, &cells, 0, 0, LEAF_SIZE));
+                continue;
+            }
+            let values: Vec<usize> = line.split_whitespace()
+                .map(|value| value.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|_| ParseError::new(line_number, format!("expected `level nw ne sw se`, found `{}`", line)))?;
+            let (level, children) = match values.split_first() {
+                Some((&level, children)) if children.len() == 4 => (u32::try_from(level).unwrap_or(u32::MAX), children),
+                _ => return Err(ParseError::new(line_number, format!("expected `level nw ne sw se`, found `{}`", line))),
+            };
+            if level <= LEAF_LEVEL {
+                return Err(ParseError::new(line_number, "multi-state macrocells are not supported"));
+            }
+            if level > MAX_LEVEL {
+                return Err(ParseError::new(line_number, format!("level {} is larger than the largest supported, {}", level, MAX_LEVEL)));
+            }
+            let mut quadrants = [0; 4];
+            for (quadrant, &child) in quadrants.iter_mut().zip(children) {
+                *quadrant = match child {
+                    0 => tree.get_empty(level - 1),
+                    _ => match ids.get(child - 1) {
+                        Some(&id) if tree.get_quad(id).level() == level - 1 => id,
+                        Some(_) => return Err(ParseError::new(line_number, format!("node {} has the wrong level", child))),
+                        None => return Err(ParseError::new(line_number, format!("node {} is not defined yet", child))),
+                    },
+                };
+            }
+            ids.push(tree.join(quadrants[0], quadrants[1], quadrants[2], quadrants[3]));
+        }
+        let root = *ids.last().ok_or_else(|| ParseError::new(text.lines().count(), "no nodes"))?;
+        tree.set_root(root);
+        Ok(tree)
+    }
+
+    /// Writes the universe as a Macrocell pattern, each distinct quad once.
+    pub fn to_macrocell(&self) -> String {
+        let mut rule = self.get_rule().to_string();
+        if *self.get_topology() != Topology::Plane {
+            rule = format!("{}:{}", rule, self.get_topology());
+        }
+        let mut text = format!("[M2] (game_of_life {})\n#R {}\n", env!("CARGO_PKG_VERSION"), rule);
+        if self.get_generation() > 0 {
+            text.push_str(&format!("#G {}\n", self.get_generation()));
+        }
+        let mut lines: HashMap<QuadId, usize> = HashMap::new();
+        let mut count = 0;
+        self.write_quad(self.get_root(), &mut lines, &mut count, &mut text);
+        text
+    }
+
+    fn write_quad(&self, id: QuadId, lines: &mut HashMap<QuadId, usize>, count: &mut usize, text: &mut String) -> usize {
+        if self.get_population_of(id) == 0 && id != self.get_root() {
+            return 0;
+        }
+        if let Some(&line) = lines.get(&id) {
+            return line;
+        }
+        match self.get_quad(id) {
+            Quad::Branch { level, .. } if level == LEAF_LEVEL => {
+                let mut cells = [[false; LEAF_SIZE]; LEAF_SIZE];
+                fill_leaf(self, id, 0, 0, &mut cells);
+                let rows: Vec<String> = cells.iter()
+                    .map(|row| {
+                        let row: String = row.iter().map(|&alive| if alive { '*' } else { '.' }).collect();
+                        row.trim_end_matches('.').to_string()
+                    })
+                    .collect();
+                let last = rows.iter().rposition(|row| !row.is_empty()).map_or(0, |last| last + 1);
+                for row in &rows[..last] {
+                    text.push_str(row);
+                    text.push('$');
+                }
+                if last == 0 {
+                    text.push('$');
+                }
+                text.push('\n');
+            }
+            Quad::Branch { level, nw, ne, sw, se } => {
+                let children: Vec<usize> = [nw, ne, sw, se].iter()
+                    .map(|&child| self.write_quad(child, lines, count, text))
+                    .collect();
+                text.push_str(&format!("{} {} {} {} {}\n", level, children[0], children[1], children[2], children[3]));
+            }
+            Quad::Leaf(_) => unreachable!("the root is at least a macrocell leaf"),
+        }
+        *count += 1;
+        lines.insert(id, *count);
+        *count
+    }
+}
+
+impl Game {
+    /// Reads a Macrocell pattern. Only patterns small enough to list every
+    /// live node should go through a `Game`; `QuadTree::from_macrocell`
+    /// handles the rest.
+    pub fn from_macrocell(text: &str) -> Result<Game, ParseError> {
+        Ok(QuadTree::from_macrocell(text)?.to_game())
+    }
+
+    /// Writes the game as Macrocell, failing on nodes too far from the origin
+    /// for a `QuadTree`.
+    pub fn to_macrocell(&self) -> Result<String, WriteError> {
+        let tree = QuadTree::from_game(self).map_err(|error| WriteError::new(error.to_string()))?;
+        Ok(tree.to_macrocell())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::node::Node;
+
+    const GLIDER: &str = "\
+[M2] (golly 2.0)
+#R B3/S23
+$$$$$$.*$..*$
+***$
+4 0 1 0 2
+";
+
+    #[test]
+    fn reads_macrocell_glider() {
+        let tree = QuadTree::from_macrocell(GLIDER).unwrap();
+        assert_eq!(tree.get_level(), 4);
+        assert_eq!(tree.get_population(), 5);
+        assert!(tree.is_alive(1, -2));
+        assert!(tree.is_alive(2, -1));
+        assert!(tree.is_alive(0, 0));
+        assert!(tree.is_alive(1, 0));
+        assert!(tree.is_alive(2, 0));
+    }
+
+    #[test]
+    fn rejects_malformed_macrocell() {
+        assert_eq!(QuadTree::from_macrocell("4 0 0 0 0\n").err().unwrap().line, 1);
+        assert_eq!(QuadTree::from_macrocell("[M2]\n4 0 0 0 1\n").err().unwrap().line, 2);
+        assert_eq!(QuadTree::from_macrocell("[M2]\n*$\n5 0 0 0 1\n").err().unwrap().line, 3);
+        assert_eq!(QuadTree::from_macrocell("[M2]\n1 0 0 0 1\n").err().unwrap().line, 2);
+        assert_eq!(QuadTree::from_macrocell("[M2]\n*********\n").err().unwrap().line, 2);
+        let too_deep = format!("[M2]\n*$\n{} 1 0 0 0\n", MAX_LEVEL + 1);
+        assert_eq!(QuadTree::from_macrocell(&too_deep).err().unwrap().line, 3);
+        assert_eq!(QuadTree::from_macrocell("[M2]\n*$\n4294967300 1 0 0 0\n").err().unwrap().line, 3);
+    }
+
+    #[test]
+    fn writes_each_shared_quad_once() {
+        let mut game = Game::new();
+        for i in 0..64 {
+            for j in 0..64 {
+                game.live_nodes.push(Node { x: i * 8, y: j * 8 });
+                game.live_nodes.push(Node { x: i * 8 + 1, y: j * 8 });
+                game.live_nodes.push(Node { x: i * 8, y: j * 8 + 1 });
+                game.live_nodes.push(Node { x: i * 8 + 1, y: j * 8 + 1 });
+            }
+        }
+        let text = game.to_macrocell().unwrap();
+        let node_lines = text.lines().filter(|line| !line.starts_with(['[', '#'])).count();
+        assert!(node_lines < 32, "{}", text);
+        let tree = QuadTree::from_macrocell(&text).unwrap();
+        assert_eq!(tree.get_population(), 64 * 64 * 4);
+    }
+
+    #[test]
+    fn round_trips_game_through_macrocell() {
+        let game = Game::from_rle("#CXRLE Pos=-40,25\nx = 36, y = 9, rule = B36/S23\n\
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b\n\
+obo$10bo5bo7bo$11bo3bo$12b2o!").unwrap();
+        let copy = Game::from_macrocell(&game.to_macrocell().unwrap()).unwrap();
+        assert_eq!(copy.get_sorted_cells(), game.get_sorted_cells());
+        assert_eq!(copy.rule, game.rule);
+        let torus = Game { topology: "T64,48".parse().unwrap(), ..game };
+        assert!(torus.to_macrocell().unwrap().contains("\n#R B36/S23:T64,48\n"));
+        assert_eq!(Game::from_macrocell(&torus.to_macrocell().unwrap()).unwrap().topology, torus.topology);
+    }
+
+    #[test]
+    fn keeps_the_generation() {
+        let mut tree = QuadTree::from_macrocell(GLIDER).unwrap();
+        tree.step(8).unwrap();
+        let copy = QuadTree::from_macrocell(&tree.to_macrocell()).unwrap();
+        assert_eq!(copy.get_generation(), 8);
+        assert_eq!(copy.get_live_cells().len(), 5);
+    }
+}
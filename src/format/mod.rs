@@ -0,0 +1,257 @@
+use std::fmt;
+use crate::box_boundary::BoxBoundary;
+use crate::game::Game;
+use crate::node::{Coord, Node};
+
+pub mod plaintext;
+pub mod rle;
+pub mod life;
+pub mod macrocell;
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        ParseError { line, message: message.into() }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, PartialEq)]
+pub struct WriteError(String);
+
+impl WriteError {
+    pub fn new(message: impl Into<String>) -> Self {
+        WriteError(message.into())
+    }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "write error: {}", self.0)
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+/// The most nodes the grid formats write a box of, so a pattern spread far
+/// apart fails instead of filling memory.
+pub const MAX_GRID_NODES: usize = 1 << 30;
+
+/// The width and height of the box, if a grid format can write it.
+pub(crate) fn get_grid_size(boundary: &BoxBoundary) -> Result<(usize, usize), WriteError> {
+    let size = |lower: Coord, upper: Coord| {
+        upper.checked_sub(lower)
+            .and_then(|size| usize::try_from(size).ok())
+            .and_then(|size| size.checked_add(1))
+    };
+    match (size(boundary.lower.x, boundary.upper.x), size(boundary.lower.y, boundary.upper.y)) {
+        (Some(width), Some(height)) if width.checked_mul(height).is_some_and(|nodes| nodes <= MAX_GRID_NODES) => {
+            Ok((width, height))
+        }
+        _ => Err(WriteError::new(format!("the pattern spans more than {} nodes", MAX_GRID_NODES))),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// The README `rows cols` grid of `*` and `.`.
+    Plaintext,
+    /// The Life Wiki `.cells` grid of `O` and `.`.
+    Cells,
+    Rle,
+    /// Life 1.05 and Xlife.
+    Life105,
+    Life106,
+    Macrocell,
+}
+
+impl Format {
+    /// Guesses the format of a pattern from its header or, for the formats
+    /// without one, from the characters it's made of.
+    pub fn detect(text: &str) -> Option<Format> {
+        let lines: Vec<&str> = text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        let first = lines.first()?;
+        if first.starts_with("#Life 1.05") {
+            return Some(Format::Life105);
+        }
+        if first.starts_with("#Life 1.06") {
+            return Some(Format::Life106);
+        }
+        if first.starts_with("[M2]") {
+            return Some(Format::Macrocell);
+        }
+        if first.starts_with("#CXRLE") {
+            return Some(Format::Rle);
+        }
+        let body: Vec<&str> = lines.iter()
+            .copied()
+            .filter(|line| !line.starts_with('#') && !line.starts_with('!'))
+            .collect();
+        if body.first().is_some_and(|line| line.starts_with('x') && line.contains('=')) {
+            return Some(Format::Rle);
+        }
+        if first.starts_with('!') {
+            return Some(Format::Cells);
+        }
+        if first.starts_with('#') {
+            return Some(Format::Life105);
+        }
+        let is_pair = |line: &&str| {
+            let values: Vec<&str> = line.split_whitespace().collect();
+            values.len() == 2 && values.iter().all(|value| value.parse::<i64>().is_ok())
+        };
+        let is_row = |line: &&str, cells: &[char]| line.chars().all(|cell| cells.contains(&cell));
+        if is_pair(first) && body[1..].iter().all(|line| is_row(line, &['*', '.'])) {
+            return Some(Format::Plaintext);
+        }
+        if body.iter().all(is_pair) {
+            return Some(Format::Life106);
+        }
+        if body.iter().all(|line| is_row(line, &['O', '.'])) {
+            return Some(Format::Cells);
+        }
+        if body.iter().all(|line| is_row(line, &['*', '.'])) {
+            return Some(Format::Life105);
+        }
+        if body.iter().all(|line| line.chars().all(|tag| tag.is_ascii_alphanumeric() || "$!.".contains(tag))) {
+            return Some(Format::Rle);
+        }
+        None
+    }
+
+    /// The format with this name or file extension, e.g. `rle` or `mc`.
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "plaintext" | "txt" => Some(Format::Plaintext),
+            "cells" => Some(Format::Cells),
+            "rle" => Some(Format::Rle),
+            "life105" => Some(Format::Life105),
+            "life106" | "lif" | "life" => Some(Format::Life106),
+            "macrocell" | "mc" => Some(Format::Macrocell),
+            _ => None,
+        }
+    }
+
+    pub fn read(&self, text: &str) -> Result<Game, ParseError> {
+        match self {
+            Format::Plaintext => Game::from_plaintext(text),
+            Format::Cells => Game::from_cells(text),
+            Format::Rle => Game::from_rle(text),
+            Format::Life105 => Game::from_life105(text),
+            Format::Life106 => Game::from_life106(text),
+            Format::Macrocell => Game::from_macrocell(text),
+        }
+    }
+
+    /// Writes the game in this format. The README grid is written for the
+    /// smallest box around the live nodes, moved to the origin, and fails
+    /// like `.cells` when that box is larger than `MAX_GRID_NODES`.
+    pub fn write(&self, game: &Game) -> Result<String, WriteError> {
+        match self {
+            Format::Plaintext => {
+                let boundary = match game.get_live_boundary() {
+                    Some(boundary) => boundary,
+                    None => return Ok(game.to_plaintext(0, 0)),
+                };
+                let (cols, rows) = get_grid_size(&boundary)?;
+                let mut moved = Game::new();
+                moved.live_nodes = game.live_nodes.iter()
+                    .map(|node| Node {
+                        x: node.x - boundary.lower.x,
+                        y: node.y - boundary.lower.y,
+                    })
+                    .collect();
+                Ok(moved.to_plaintext(rows, cols))
+            }
+            Format::Cells => game.to_cells(),
+            Format::Rle => game.to_rle(),
+            Format::Life105 => game.to_life105(),
+            Format::Life106 => game.to_life106(),
+            Format::Macrocell => game.to_macrocell(),
+        }
+    }
+}
+
+impl Game {
+    /// Reads a pattern in any of the supported formats, detected from its header.
+    pub fn from_pattern(text: &str) -> Result<Game, ParseError> {
+        Format::detect(text)
+            .ok_or_else(|| ParseError::new(1, "unknown pattern format"))?
+            .read(text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detects_formats_by_header() {
+        assert_eq!(Format::detect("#Life 1.05\n#P 0 0\n*\n"), Some(Format::Life105));
+        assert_eq!(Format::detect("#Life 1.06\n0 0\n"), Some(Format::Life106));
+        assert_eq!(Format::detect("#N Glider\nx = 3, y = 3\nbo$2bo$3o!"), Some(Format::Rle));
+        assert_eq!(Format::detect("#CXRLE Pos=0,0\nx = 1, y = 1\no!"), Some(Format::Rle));
+        assert_eq!(Format::detect("!Name: Glider\n.O\n..O\nOOO\n"), Some(Format::Cells));
+        assert_eq!(Format::detect("#P 0 0\n.*\n"), Some(Format::Life105));
+        assert_eq!(Format::detect("[M2] (golly 2.0)\n#R B3/S23\n*$\n"), Some(Format::Macrocell));
+    }
+
+    #[test]
+    fn detects_formats_without_header() {
+        assert_eq!(Format::detect("2 3\n*..\n.**\n"), Some(Format::Plaintext));
+        assert_eq!(Format::detect("0 0\n1 0\n2 0\n"), Some(Format::Life106));
+        assert_eq!(Format::detect(".O\n..O\nOOO\n"), Some(Format::Cells));
+        assert_eq!(Format::detect(".*\n..*\n***\n"), Some(Format::Life105));
+        assert_eq!(Format::detect("bo$2bo$3o!"), Some(Format::Rle));
+        assert_eq!(Format::detect(""), None);
+        assert_eq!(Format::detect("hello, world"), None);
+    }
+
+    #[test]
+    fn finds_formats_by_name_or_extension() {
+        assert_eq!(Format::from_name("RLE"), Some(Format::Rle));
+        assert_eq!(Format::from_name("mc"), Some(Format::Macrocell));
+        assert_eq!(Format::from_name("lif"), Some(Format::Life106));
+        assert_eq!(Format::from_name("life105"), Some(Format::Life105));
+        assert_eq!(Format::from_name("png"), None);
+    }
+
+    #[test]
+    fn reads_the_same_glider_from_every_format() {
+        let glider = Game::from_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+        for format in [Format::Plaintext, Format::Cells, Format::Rle, Format::Life105, Format::Life106, Format::Macrocell] {
+            let text = format.write(&glider).unwrap();
+            assert_eq!(Format::detect(&text), Some(format), "{}", text);
+            let copy = Game::from_pattern(&text).unwrap();
+            assert_eq!(copy.live_nodes.len(), 5);
+            assert_eq!(copy.to_cells(), glider.to_cells());
+        }
+    }
+
+    #[test]
+    fn rejects_grids_too_large_to_write() {
+        let mut game = Game::new();
+        game.live_nodes = vec![Node { x: -(1 << 62), y: 0 }, Node { x: 1 << 62, y: 0 }];
+        assert!(Format::Plaintext.write(&game).is_err());
+        assert!(game.to_cells().is_err());
+        game.live_nodes = vec![Node { x: Coord::MIN, y: Coord::MIN }, Node { x: Coord::MAX, y: Coord::MAX }];
+        assert!(Format::Plaintext.write(&game).is_err());
+        game.live_nodes = vec![Node { x: 1 << 40, y: 5 }, Node { x: (1 << 40) + 2, y: 4 }];
+        assert_eq!(Format::Plaintext.write(&game).unwrap(), "2 3\n*..\n..*\n");
+    }
+}
@@ -0,0 +1,202 @@
+use crate::format::{get_grid_size, ParseError, WriteError};
+use crate::game::Game;
+use crate::node::{Coord, Node};
+
+impl Game {
+    /// Reads the README grid format: a `rows cols` header followed by `rows`
+    /// lines of `cols` cells, `*` for a live node and `.` for a dead one.
+    /// The bottom row is y = 0 and the leftmost column x = 0.
+    pub fn from_plaintext(text: &str) -> Result<Game, ParseError> {
+        let mut lines = text.lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+        let (header_line, header) = lines.next()
+            .ok_or_else(|| ParseError::new(1, "missing `rows cols` header"))?;
+        let mut size = header.split_whitespace().map(|value| value.parse::<usize>());
+        let (rows, cols) = match (size.next(), size.next(), size.next()) {
+            (Some(Ok(rows)), Some(Ok(cols)), None) => (rows, cols),
+            _ => return Err(ParseError::new(header_line, "expected `rows cols` header")),
+        };
+        let mut game = Game::new();
+        let mut row = 0;
+        for (line_number, line) in lines {
+            if row == rows {
+                return Err(ParseError::new(line_number, format!("expected {} rows", rows)));
+            }
+            if line.chars().count() != cols {
+                return Err(ParseError::new(line_number, format!("expected {} columns", cols)));
+            }
+            let y = (rows - 1 - row) as Coord;
+            for (x, cell) in line.chars().enumerate() {
+                match cell {
+                    '*' => game.live_nodes.push(Node { x: x as Coord, y }),
+                    '.' => {}
+                    _ => return Err(ParseError::new(line_number, format!("unexpected `{}`", cell))),
+                }
+            }
+            row += 1;
+        }
+        if row != rows {
+            return Err(ParseError::new(text.lines().count(), format!("expected {} rows", rows)));
+        }
+        Ok(game)
+    }
+
+    /// Writes the `rows` by `cols` grid whose bottom left node is at 0, 0 in
+    /// the README grid format. Live nodes outside of it are left out.
+    pub fn to_plaintext(&self, rows: usize, cols: usize) -> String {
+        let mut grid = vec![vec!['.'; cols]; rows];
+        for node in &self.live_nodes {
+            let x = usize::try_from(node.x).ok().filter(|&x| x < cols);
+            let row = usize::try_from(node.y).ok().filter(|&y| y < rows).map(|y| rows - 1 - y);
+            if let (Some(x), Some(row)) = (x, row) {
+                grid[row][x] = '*';
+            }
+        }
+        let mut text = format!("{} {}\n", rows, cols);
+        for row in grid {
+            text.extend(row);
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Reads the Life Wiki `.cells` format: `!` comment lines followed by rows
+    /// of `O` for a live node and `.` for a dead one. Rows may leave out their
+    /// trailing dead nodes. The bottom row is y = 0 and the leftmost column x = 0.
+    pub fn from_cells(text: &str) -> Result<Game, ParseError> {
+        let rows: Vec<(usize, &str)> = text.lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim_end()))
+            .filter(|(_, line)| !line.starts_with('!'))
+            .collect();
+        let mut game = Game::new();
+        for (row, (line_number, line)) in rows.iter().enumerate() {
+            let y = (rows.len() - 1 - row) as Coord;
+            for (x, cell) in line.chars().enumerate() {
+                match cell {
+                    'O' | '*' => game.live_nodes.push(Node { x: x as Coord, y }),
+                    '.' => {}
+                    _ => return Err(ParseError::new(*line_number, format!("unexpected `{}`", cell))),
+                }
+            }
+        }
+        Ok(game)
+    }
+
+    /// Writes the live nodes in the Life Wiki `.cells` format, cropped to the
+    /// smallest box around them, or fails if that box is larger than
+    /// `MAX_GRID_NODES`.
+    pub fn to_cells(&self) -> Result<String, WriteError> {
+        let boundary = match self.get_live_boundary() {
+            Some(boundary) => boundary,
+            None => return Ok(String::new()),
+        };
+        let (_, height) = get_grid_size(&boundary)?;
+        let mut lines = vec![Vec::new(); height];
+        for (x, y) in self.get_sorted_cells() {
+            let line = &mut lines[(boundary.upper.y - y) as usize];
+            let column = (x - boundary.lower.x) as usize;
+            line.resize(column, '.');
+            line.push('O');
+        }
+        let mut text = String::new();
+        for line in lines {
+            text.extend(line);
+            text.push('\n');
+        }
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reads_plaintext_grid() {
+        let game = Game::from_plaintext("2 3\n*..\n.**\n").unwrap();
+        assert_eq!(game.live_nodes, vec![Node { x: 0, y: 1 }, Node { x: 1, y: 0 }, Node { x: 2, y: 0 }]);
+    }
+
+    #[test]
+    fn rejects_malformed_plaintext_grid() {
+        assert_eq!(Game::from_plaintext("").err().unwrap().line, 1);
+        assert_eq!(Game::from_plaintext("2\n").err().unwrap().line, 1);
+        assert_eq!(Game::from_plaintext("2 3\n*..\n.*\n").err().unwrap().line, 3);
+        assert_eq!(Game::from_plaintext("2 3\n*..\n.o.\n").err().unwrap().line, 3);
+        assert_eq!(Game::from_plaintext("2 3\n*..\n").err().unwrap().line, 2);
+        assert_eq!(Game::from_plaintext("1 3\n*..\n...\n").err().unwrap().line, 3);
+    }
+
+    #[test]
+    fn readme_example_1_passes() {
+        let mut game = Game::from_plaintext("\
+4 8
+........
+....*...
+...**...
+.....*..
+").unwrap();
+        game.evolve();
+        assert_eq!(game.to_plaintext(4, 8), "\
+4 8
+........
+...**...
+...***..
+....*...
+");
+    }
+
+    #[test]
+    fn readme_example_2_passes() {
+        let mut game = Game::from_plaintext("\
+5 8
+........
+...**...
+.*****..
+........
+........
+").unwrap();
+        game.evolve();
+        assert_eq!(game.to_plaintext(5, 8), "\
+5 8
+........
+.....*..
+..*..*..
+..***...
+........
+");
+    }
+
+    #[test]
+    fn reads_cells_with_comments_and_short_rows() {
+        let game = Game::from_cells("!Name: Glider\n!\n.O\n..O\nOOO\n").unwrap();
+        assert_eq!(game.live_nodes.len(), 5);
+        assert!(game.is_node_alive(1, 2));
+        assert!(game.is_node_alive(2, 1));
+        assert!(game.is_node_alive(0, 0));
+        assert!(game.is_node_alive(1, 0));
+        assert!(game.is_node_alive(2, 0));
+    }
+
+    #[test]
+    fn rejects_unexpected_cells_character() {
+        assert_eq!(Game::from_cells("!Name: Glider\n.O\n..x\n").err().unwrap().line, 3);
+    }
+
+    #[test]
+    fn writes_cells_cropped_to_live_nodes() {
+        let mut game = Game::new();
+        game.live_nodes = vec![
+            Node { x: 11, y: -8 },
+            Node { x: 12, y: -9 },
+            Node { x: 10, y: -10 },
+            Node { x: 11, y: -10 },
+            Node { x: 12, y: -10 },
+        ];
+        assert_eq!(game.to_cells().unwrap(), ".O\n..O\nOOO\n");
+        assert_eq!(Game::new().to_cells().unwrap(), "");
+    }
+}
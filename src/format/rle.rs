@@ -0,0 +1,520 @@
+use std::fmt;
+use crate::format::{ParseError, WriteError};
+use crate::game::Game;
+use crate::node::{Coord, Node};
+use crate::rule::Rule;
+use crate::topology::Topology;
+
+const LINE_LENGTH: usize = 70;
+
+/// The most live cells an RLE pattern is read with, so that a huge run count
+/// fails instead of running out of memory.
+pub const MAX_CELLS: usize = 1 << 26;
+
+/// A node of an RLE pattern, counted in columns right and rows down from the
+/// pattern's top left corner.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RleCell {
+    pub column: u64,
+    pub row: u64,
+    pub state: u8,
+}
+
+/// An RLE pattern as written in the file, keeping the multi-state cells and
+/// comment lines that a two state `Game` has no room for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rle {
+    pub width: u64,
+    pub height: u64,
+    pub rule: Option<String>,
+    /// The top left corner from Golly's `#CXRLE Pos=x,y` line, y pointing down.
+    pub position: Option<(Coord, Coord)>,
+    pub comments: Vec<String>,
+    pub cells: Vec<RleCell>,
+}
+
+/// Where in the text `Rle::parse_lines` found the rule and the first cell in a
+/// state above 1, for error messages about them.
+struct Lines {
+    rule: Option<usize>,
+    multi_state: Option<usize>,
+}
+
+impl Rle {
+    pub fn parse(text: &str) -> Result<Rle, ParseError> {
+        Rle::parse_lines(text).map(|(rle, _)| rle)
+    }
+
+    fn parse_lines(text: &str) -> Result<(Rle, Lines), ParseError> {
+        let mut rle = Rle {
+            width: 0,
+            height: 0,
+            rule: None,
+            position: None,
+            comments: Vec::new(),
+            cells: Vec::new(),
+        };
+        let mut has_header = false;
+        let mut rule_line = None;
+        let mut body = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(comment) = line.strip_prefix('#') {
+                if let Some(extension) = comment.strip_prefix("CXRLE") {
+                    rle.position = parse_position(extension)
+                        .map_err(|message| ParseError::new(line_number, message))?;
+                } else if let Some(rule) = comment.strip_prefix('r') {
+                    rle.rule = Some(rule.trim().to_string());
+                    rule_line = Some(line_number);
+                } else {
+                    rle.comments.push(line.to_string());
+                }
+            } else if !has_header && body.is_empty() && line.starts_with('x') {
+                if parse_header(line, &mut rle).map_err(|message| ParseError::new(line_number, message))? {
+                    rule_line = Some(line_number);
+                }
+                has_header = true;
+            } else {
+                body.push((line_number, line));
+            }
+        }
+        let width = if has_header { Some(rle.width) } else { None };
+        let multi_state_line = parse_body(&body, width, &mut rle.cells)?;
+        if !has_header {
+            rle.width = rle.cells.iter().map(|cell| cell.column + 1).max().unwrap_or(0);
+            rle.height = rle.cells.iter().map(|cell| cell.row + 1).max().unwrap_or(0);
+        }
+        Ok((rle, Lines { rule: rule_line, multi_state: multi_state_line }))
+    }
+
+    fn is_multi_state(&self) -> bool {
+        self.cells.iter().any(|cell| cell.state > 1)
+    }
+}
+
+fn parse_position(extension: &str) -> Result<Option<(Coord, Coord)>, String> {
+    for field in extension.split_whitespace() {
+        if let Some(position) = field.strip_prefix("Pos=") {
+            let (x, y) = position.split_once(',').ok_or("expected `Pos=x,y`")?;
+            let x = x.parse().map_err(|_| "invalid x position")?;
+            let y = y.parse().map_err(|_| "invalid y position")?;
+            return Ok(Some((x, y)));
+        }
+    }
+    Ok(None)
+}
+
+/// Reads the `x = 3, y = 3, rule = B3/S23` line, returning whether it had a rule.
+fn parse_header(line: &str, rle: &mut Rle) -> Result<bool, String> {
+    let mut rest = line;
+    while let Some((key, after)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim();
+        if key == "rule" {
+            rle.rule = Some(after.trim().to_string());
+            return Ok(true);
+        }
+        let (value, next) = after.split_once(',').unwrap_or((after, ""));
+        let value = value.trim();
+        match key {
+            "x" => rle.width = value.parse().map_err(|_| format!("invalid width `{}`", value))?,
+            "y" => rle.height = value.parse().map_err(|_| format!("invalid height `{}`", value))?,
+            _ => return Err(format!("unknown header field `{}`", key)),
+        }
+        rest = next;
+    }
+    Ok(false)
+}
+
+/// Reads the cells, returning the line of the first one in a state above 1.
+/// Runs longer than the header's width are rejected.
+fn parse_body(body: &[(usize, &str)], width: Option<u64>, cells: &mut Vec<RleCell>) -> Result<Option<usize>, ParseError> {
+    // Wider than the cells so that moving past the last column can't overflow.
+    let (mut column, mut row) = (0u128, 0u128);
+    let mut run: Option<u64> = None;
+    let mut prefix: Option<char> = None;
+    let mut multi_state_line = None;
+    for &(line_number, line) in body {
+        let error = |message: String| ParseError::new(line_number, message);
+        for tag in line.chars() {
+            if let Some(high) = prefix.take() {
+                let state = match tag {
+                    'A'..='X' => 24 * (high as u32 - 'p' as u32 + 1) + (tag as u32 - 'A' as u32 + 1),
+                    _ => return Err(error(format!("unexpected `{}{}`", high, tag))),
+                };
+                let state = u8::try_from(state).map_err(|_| error(format!("state {} is too high", state)))?;
+                multi_state_line = multi_state_line.or(Some(line_number));
+                let count = run.take().unwrap_or(1);
+                push_run(cells, column, row, count, state, width).map_err(error)?;
+                column += count as u128;
+                continue;
+            }
+            match tag {
+                '0'..='9' => {
+                    let digit = tag as u64 - '0' as u64;
+                    run = Some(run.unwrap_or(0).checked_mul(10)
+                        .and_then(|run| run.checked_add(digit))
+                        .ok_or_else(|| error("run count is too long".to_string()))?);
+                }
+                'p'..='y' => prefix = Some(tag),
+                'b' | '.' => column += run.take().unwrap_or(1) as u128,
+                'o' | 'A'..='X' => {
+                    let state = if tag == 'o' { 1 } else { tag as u8 - b'A' + 1 };
+                    if state > 1 {
+                        multi_state_line = multi_state_line.or(Some(line_number));
+                    }
+                    let count = run.take().unwrap_or(1);
+                    push_run(cells, column, row, count, state, width).map_err(error)?;
+                    column += count as u128;
+                }
+                '$' => {
+                    row += run.take().unwrap_or(1) as u128;
+                    column = 0;
+                }
+                '!' => return Ok(multi_state_line),
+                _ if tag.is_whitespace() => {}
+                _ => return Err(error(format!("unexpected `{}`", tag))),
+            }
+        }
+    }
+    Ok(multi_state_line)
+}
+
+fn push_run(cells: &mut Vec<RleCell>, column: u128, row: u128, count: u64, state: u8, width: Option<u64>) -> Result<(), String> {
+    if let Some(width) = width.filter(|&width| count > width) {
+        return Err(format!("run of {} is longer than the width x = {}", count, width));
+    }
+    if count > (MAX_CELLS - cells.len()) as u64 {
+        return Err(format!("the pattern has more than {} cells", MAX_CELLS));
+    }
+    let too_large = |_| "the pattern is larger than RLE can count".to_string();
+    let row = u64::try_from(row).map_err(too_large)?;
+    for offset in 0..count {
+        let column = u64::try_from(column + offset as u128).map_err(too_large)?;
+        cells.push(RleCell { column, row, state });
+    }
+    Ok(())
+}
+
+fn state_tag(state: u8, multi_state: bool) -> String {
+    match (state, multi_state) {
+        (0, false) => "b".to_string(),
+        (_, false) => "o".to_string(),
+        (0, true) => ".".to_string(),
+        (1..=24, true) => ((b'A' + state - 1) as char).to_string(),
+        (_, true) => {
+            let high = (b'p' + (state - 25) / 24) as char;
+            let low = (b'A' + (state - 25) % 24) as char;
+            format!("{}{}", high, low)
+        }
+    }
+}
+
+fn run_token(count: u64, tag: &str) -> String {
+    if count == 1 {
+        tag.to_string()
+    } else {
+        format!("{}{}", count, tag)
+    }
+}
+
+impl fmt::Display for Rle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some((x, y)) = self.position {
+            writeln!(f, "#CXRLE Pos={},{}", x, y)?;
+        }
+        for comment in &self.comments {
+            writeln!(f, "{}", comment)?;
+        }
+        write!(f, "x = {}, y = {}", self.width, self.height)?;
+        match &self.rule {
+            Some(rule) => writeln!(f, ", rule = {}", rule)?,
+            None => writeln!(f)?,
+        }
+
+        let multi_state = self.is_multi_state();
+        let mut cells = self.cells.clone();
+        cells.sort_by_key(|cell| (cell.row, cell.column));
+        let mut tokens = Vec::new();
+        let (mut column, mut row) = (0u64, 0u64);
+        let mut index = 0;
+        while index < cells.len() {
+            let cell = &cells[index];
+            if cell.row > row {
+                tokens.push(run_token(cell.row - row, "$"));
+                row = cell.row;
+                column = 0;
+            }
+            if cell.column > column {
+                tokens.push(run_token(cell.column - column, &state_tag(0, multi_state)));
+            }
+            let mut count = 1;
+            while index + count < cells.len()
+                && cells[index + count].row == cell.row
+                && Some(cells[index + count].column) == cell.column.checked_add(count as u64)
+                && cells[index + count].state == cell.state {
+                count += 1;
+            }
+            tokens.push(run_token(count as u64, &state_tag(cell.state, multi_state)));
+            column = cell.column.saturating_add(count as u64);
+            index += count;
+        }
+        tokens.push("!".to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + token.len() > LINE_LENGTH {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+/// How far `to` is from `from`, as an RLE width or column. RLE can't count
+/// past `u64::MAX`, which only 128 bit coordinates reach.
+// The difference is already a u64 unless `wide-coordinates` is on.
+#[allow(clippy::useless_conversion)]
+fn get_span(from: Coord, to: Coord) -> Result<u64, WriteError> {
+    u64::try_from(to.abs_diff(from))
+        .map_err(|_| WriteError::new("the pattern is wider than RLE can count"))
+}
+
+impl Game {
+    /// Reads a two state RLE pattern. A rule with a Golly bounded grid suffix,
+    /// e.g. `B3/S23:T64,48`, sets the topology. Without a `#CXRLE Pos` line the
+    /// pattern's bottom left corner is placed at 0, 0. Multi-state patterns,
+    /// and rules that aren't life-like such as LifeHistory or Generations, are
+    /// rejected rather than losing their states; `Rle::parse` keeps them.
+    pub fn from_rle(text: &str) -> Result<Game, ParseError> {
+        let (rle, lines) = Rle::parse_lines(text)?;
+        let mut game = Game::new();
+        if let Some(rule) = &rle.rule {
+            let rule_line = lines.rule.unwrap_or(1);
+            let (rule, topology) = rule.split_once(':').unwrap_or((rule, ""));
+            game.rule = rule.parse::<Rule>().map_err(|error| {
+                ParseError::new(rule_line, format!("{}, only two state life-like rules are supported", error))
+            })?;
+            game.topology = topology.parse::<Topology>()
+                .map_err(|error| ParseError::new(rule_line, error.to_string()))?;
+        }
+        if let Some(line) = lines.multi_state {
+            return Err(ParseError::new(line, "cells in states above 1 need a multi-state rule".to_string()));
+        }
+        let (left, top) = match rle.position {
+            Some((x, y)) => (x, y.checked_neg().ok_or_else(|| ParseError::new(1, format!("y position {} is out of range", y)))?),
+            None => {
+                let height = Coord::default().checked_add_unsigned(rle.height as _)
+                    .ok_or_else(|| ParseError::new(1, format!("height {} is out of range", rle.height)))?;
+                (0, height - 1)
+            }
+        };
+        game.live_nodes = rle.cells.iter()
+            .filter_map(|cell| Some(Node {
+                x: left.checked_add_unsigned(cell.column as _)?,
+                y: top.checked_sub_unsigned(cell.row as _)?,
+            }))
+            .collect();
+        Ok(game)
+    }
+
+    /// Writes the live nodes as an RLE pattern with the rule, the topology and
+    /// the pattern's position, so that `from_rle` gives back the same game.
+    /// Fails on patterns wider or taller than RLE can count.
+    pub fn to_rle(&self) -> Result<String, WriteError> {
+        let mut rule = self.rule.to_string();
+        if self.topology != Topology::Plane {
+            rule = format!("{}:{}", rule, self.topology);
+        }
+        let mut rle = Rle {
+            width: 0,
+            height: 0,
+            rule: Some(rule),
+            position: None,
+            comments: Vec::new(),
+            cells: Vec::new(),
+        };
+        if let Some(boundary) = self.get_live_boundary() {
+            let top = boundary.upper.y.checked_neg()
+                .ok_or_else(|| WriteError::new(format!("y position {} is out of range", boundary.upper.y)))?;
+            rle.width = get_span(boundary.lower.x, boundary.upper.x)?.saturating_add(1);
+            rle.height = get_span(boundary.lower.y, boundary.upper.y)?.saturating_add(1);
+            rle.position = Some((boundary.lower.x, top));
+            rle.cells = self.live_nodes.iter()
+                .map(|node| Ok(RleCell {
+                    column: get_span(boundary.lower.x, node.x)?,
+                    row: get_span(node.y, boundary.upper.y)?,
+                    state: 1,
+                }))
+                .collect::<Result<_, WriteError>>()?;
+        }
+        Ok(rle.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::assert_same_nodes;
+
+    const GLIDER_GUN: &str = "\
+#N Gosper glider gun
+#O Bill Gosper
+#C A true period 30 glider gun.
+x = 36, y = 9, rule = B3/S23
+24bo$22bobo$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o$2o8bo3bob2o4b
+obo$10bo5bo7bo$11bo3bo$12b2o!
+";
+
+    #[test]
+    fn reads_header_comments_and_wrapped_body() {
+        let rle = Rle::parse(GLIDER_GUN).unwrap();
+        assert_eq!(rle.width, 36);
+        assert_eq!(rle.height, 9);
+        assert_eq!(rle.rule, Some("B3/S23".to_string()));
+        assert_eq!(rle.comments.len(), 3);
+        assert_eq!(rle.cells.len(), 36);
+        assert_eq!(rle.cells[0], RleCell { column: 24, row: 0, state: 1 });
+        assert!(rle.cells.contains(&RleCell { column: 35, row: 3, state: 1 }));
+        assert!(rle.cells.contains(&RleCell { column: 13, row: 8, state: 1 }));
+    }
+
+    #[test]
+    fn reads_glider_with_its_bottom_left_corner_at_origin() {
+        let game = Game::from_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+        assert_same_nodes(&game.live_nodes, &[
+            Node { x: 1, y: 2 },
+            Node { x: 2, y: 1 },
+            Node { x: 0, y: 0 },
+            Node { x: 1, y: 0 },
+            Node { x: 2, y: 0 },
+        ]);
+        assert_eq!(game.rule, Rule::conway());
+        assert_eq!(game.topology, Topology::Plane);
+    }
+
+    #[test]
+    fn reads_rule_and_topology() {
+        let game = Game::from_rle("x = 2, y = 2, rule = B36/S23:T64,48\n2o$2o!").unwrap();
+        assert_eq!(game.rule, "B36/S23".parse().unwrap());
+        assert_eq!(game.topology, Topology::Torus { width: 64, height: 48 });
+        assert!(Game::from_rle("x = 2, y = 2, rule = Q3\n2o$2o!").is_err());
+    }
+
+    #[test]
+    fn rejects_multi_state_patterns_and_rules_as_games() {
+        let error = Game::from_rle("#N LifeHistory\nx = 2, y = 1, rule = LifeHistory\n2A!").err().unwrap();
+        assert_eq!(error.line, 2);
+        assert!(error.to_string().contains("life-like"));
+        let error = Game::from_rle("x = 3, y = 2\n3o$\nb2B!").err().unwrap();
+        assert_eq!(error.line, 3);
+        assert_eq!(Game::from_rle("#r B03/S23\n2o!").err().unwrap().line, 1);
+        assert!(Rle::parse("x = 2, y = 1, rule = LifeHistory\n2A!").is_ok());
+    }
+
+    #[test]
+    fn round_trips_the_widest_span() {
+        let game = Game {
+            live_nodes: vec![Node { x: i64::MIN as Coord, y: 0 }, Node { x: i64::MAX as Coord, y: 1 }],
+            ..Game::new()
+        };
+        let text = game.to_rle().unwrap();
+        assert!(text.contains("x = 18446744073709551615, y = 2"));
+        assert_same_nodes(&Game::from_rle(&text).unwrap().live_nodes, &game.live_nodes);
+    }
+
+    #[test]
+    fn reads_multi_state_letters() {
+        let rle = Rle::parse("x = 5, y = 2, rule = Generations\n.A2B$pAyO!").unwrap();
+        assert_eq!(rle.cells, vec![
+            RleCell { column: 1, row: 0, state: 1 },
+            RleCell { column: 2, row: 0, state: 2 },
+            RleCell { column: 3, row: 0, state: 2 },
+            RleCell { column: 0, row: 1, state: 25 },
+            RleCell { column: 1, row: 1, state: 255 },
+        ]);
+        assert!(rle.to_string().ends_with(".A2B$pAyO!\n"));
+    }
+
+    #[test]
+    fn reads_run_counts_on_line_ends() {
+        let rle = Rle::parse("x = 1, y = 4\no3$o!").unwrap();
+        assert_eq!(rle.cells[1], RleCell { column: 0, row: 3, state: 1 });
+    }
+
+    #[test]
+    fn rejects_malformed_rle() {
+        assert_eq!(Rle::parse("x = 3, y = 3\nbo$2bo$3?!").err().unwrap().line, 2);
+        assert_eq!(Rle::parse("x = a, y = 3\n3o!").err().unwrap().line, 1);
+        assert_eq!(Rle::parse("x = 1, y = 1\nyZ!").err().unwrap().line, 2);
+        assert_eq!(Rle::parse("x = 1, y = 1\n18446744073709551615b2o!").err().unwrap().line, 2);
+    }
+
+    #[test]
+    fn writes_runs_and_wraps_lines() {
+        let rle = Rle::parse(GLIDER_GUN).unwrap();
+        let text = rle.to_string();
+        assert!(text.lines().all(|line| line.len() <= LINE_LENGTH));
+        assert!(text.starts_with("#N Gosper glider gun\n"));
+        assert!(text.contains("x = 36, y = 9, rule = B3/S23\n24bo$22bobo$12b2o6b2o12b2o$"));
+        assert_eq!(Rle::parse(&text).unwrap(), rle);
+    }
+
+    #[test]
+    fn round_trips_game_through_rle() {
+        let mut game = Game::from_rle(GLIDER_GUN).unwrap();
+        game.live_nodes.iter_mut().for_each(|node| {
+            node.x -= 100;
+            node.y += 7;
+        });
+        game.topology = "K80*,60".parse().unwrap();
+        game.rule = "B36/S23".parse().unwrap();
+        let text = game.to_rle().unwrap();
+        assert!(text.starts_with("#CXRLE Pos=-100,-15\nx = 36, y = 9, rule = B36/S23:K80*,60\n"));
+        let copy = Game::from_rle(&text).unwrap();
+        assert_same_nodes(&copy.live_nodes, &game.live_nodes);
+        assert_eq!(copy.rule, game.rule);
+        assert_eq!(copy.topology, game.topology);
+    }
+
+    #[test]
+    fn writes_empty_game() {
+        assert_eq!(Game::new().to_rle().unwrap(), "x = 0, y = 0, rule = B3/S23\n!\n");
+        assert!(Game::from_rle(&Game::new().to_rle().unwrap()).unwrap().live_nodes.is_empty());
+    }
+
+    #[test]
+    fn rejects_runs_too_long_to_hold() {
+        let error = Rle::parse("x = 3, y = 1\n4000000000o!").err().unwrap();
+        assert_eq!(error.line, 2);
+        assert!(error.message.contains("width x = 3"));
+        let error = Rle::parse("4000000000o!").err().unwrap();
+        assert!(error.message.contains("more than"));
+        assert!(Rle::parse("x = 3, y = 1\n3o!").is_ok());
+    }
+
+    #[test]
+    fn rejects_heights_past_the_coordinates() {
+        let error = Game::from_rle("x = 1, y = 18446744073709551615\no!");
+        #[cfg(not(feature = "wide-coordinates"))]
+        assert_eq!(error.err().unwrap().line, 1);
+        #[cfg(feature = "wide-coordinates")]
+        assert!(error.is_ok());
+    }
+
+    #[test]
+    fn fails_to_write_what_rle_cannot_count() {
+        let game = Game { live_nodes: vec![Node { x: 0, y: Coord::MIN }], ..Game::new() };
+        assert!(game.to_rle().err().unwrap().to_string().contains("out of range"));
+        #[cfg(feature = "wide-coordinates")]
+        {
+            let game = Game { live_nodes: vec![Node { x: Coord::MIN, y: 0 }, Node { x: Coord::MAX, y: 0 }], ..Game::new() };
+            assert!(game.to_rle().is_err());
+        }
+    }
+}
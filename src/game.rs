@@ -1,13 +1,28 @@
+use serde::{Deserialize, Serialize};
 use crate::box_boundary::BoxBoundary;
-use crate::node::Node;
+use crate::node::{Coord, Node};
+use crate::rule::Rule;
+use crate::topology::{ParseTopologyError, Topology};
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Game {
     pub live_nodes: Vec<Node>,
+    pub topology: Topology,
+    pub rule: Rule,
 }
 
 impl Game {
     pub fn new() -> Self {
-        Game { live_nodes: Vec::new() }
+        Game { live_nodes: Vec::new(), topology: Topology::Plane, rule: Rule::conway() }
+    }
+
+    /// An empty game on the grid, if `Topology::validate` accepts it.
+    pub fn with_topology(topology: Topology) -> Result<Self, ParseTopologyError> {
+        Ok(Game { topology: topology.validate()?, ..Game::new() })
+    }
+
+    pub fn with_rule(rule: Rule) -> Self {
+        Game { rule, ..Game::new() }
     }
 
     pub fn evolve(&mut self) {
@@ -24,12 +39,12 @@ impl Game {
         self.live_nodes = new_live_nodes;
     }
 
-    pub fn is_node_alive(&self, x: i32, y: i32) -> bool {
+    pub fn is_node_alive(&self, x: Coord, y: Coord) -> bool {
         self.live_nodes.contains(&Node { x, y })
     }
 
     pub fn get_live_neighbors(&self, node: &Node) -> Vec<Node> {
-        let neighbors = node.get_neighbors();
+        let neighbors = node.get_wrapped_neighbors(&self.topology);
         let live_neighbors = neighbors
             .into_iter()
             .filter(|node| self.live_nodes.contains(node))
@@ -38,42 +53,69 @@ impl Game {
     }
 
     pub fn live_node_should_die(&self, live_neighbors: &Vec<Node>) -> bool {
-        live_neighbors.len() < 2 || live_neighbors.len() > 3
+        !self.rule.survives(live_neighbors.len())
     }
 
     pub fn dead_node_should_live(&self, live_neighbors: &Vec<Node>) -> bool {
-        live_neighbors.len() == 3
+        self.rule.is_born(live_neighbors.len())
     }
 
     pub fn get_boundary(&self) -> BoxBoundary {
-        let mut box_boundary = BoxBoundary::new();
         if self.live_nodes.is_empty() {
-            box_boundary
-        } else {
-            let x_min = self.live_nodes.clone().into_iter()
-                .map(|node| node.x).min().unwrap();
-            let x_max = self.live_nodes.clone().into_iter()
-                .map(|node| node.x).max().unwrap();
-            let y_min = self.live_nodes.clone().into_iter()
-                .map(|node| node.y).min().unwrap();
-            let y_max = self.live_nodes.clone().into_iter()
-                .map(|node| node.y).max().unwrap();
-            box_boundary.lower.x = x_min - 1;
-            box_boundary.upper.x = x_max + 1;
-            box_boundary.lower.y = y_min - 1;
-            box_boundary.upper.y = y_max + 1;
-            box_boundary
+            return BoxBoundary::new();
+        }
+        let box_boundary = self.get_neighborhood_boundary();
+        match self.topology.get_bounds() {
+            Some(bounds) if !bounds.contains(&box_boundary.lower)
+                || !bounds.contains(&box_boundary.upper) => bounds,
+            _ => box_boundary,
         }
     }
 
-    fn should_node_live(&mut self, x: i32, y: i32, live_neighbors: &Vec<Node>) -> bool {
+    /// The smallest box containing every live node, `None` when there are none.
+    pub fn get_live_boundary(&self) -> Option<BoxBoundary> {
+        let mut nodes = self.live_nodes.iter();
+        let first = nodes.next()?;
+        let mut box_boundary = BoxBoundary { lower: first.clone(), upper: first.clone() };
+        for node in nodes {
+            box_boundary.lower.x = box_boundary.lower.x.min(node.x);
+            box_boundary.upper.x = box_boundary.upper.x.max(node.x);
+            box_boundary.lower.y = box_boundary.lower.y.min(node.y);
+            box_boundary.upper.y = box_boundary.upper.y.max(node.y);
+        }
+        Some(box_boundary)
+    }
+
+    /// The live cells as x, y pairs, sorted and each only once, for comparing
+    /// and hashing games whatever the order of their live nodes.
+    pub fn get_sorted_cells(&self) -> Vec<(Coord, Coord)> {
+        let mut cells: Vec<(Coord, Coord)> = self.live_nodes.iter().map(|node| (node.x, node.y)).collect();
+        cells.sort_unstable();
+        cells.dedup();
+        cells
+    }
+
+    fn get_neighborhood_boundary(&self) -> BoxBoundary {
+        let mut box_boundary = self.get_live_boundary().unwrap_or_else(BoxBoundary::new);
+        box_boundary.lower.x = box_boundary.lower.x.saturating_sub(1);
+        box_boundary.upper.x = box_boundary.upper.x.saturating_add(1);
+        box_boundary.lower.y = box_boundary.lower.y.saturating_sub(1);
+        box_boundary.upper.y = box_boundary.upper.y.saturating_add(1);
+        box_boundary
+    }
+
+    fn should_node_live(&mut self, x: Coord, y: Coord, live_neighbors: &Vec<Node>) -> bool {
         (self.is_node_alive(x, y) && !self.live_node_should_die(&live_neighbors))
             || (!self.is_node_alive(x, y) && self.dead_node_should_live(&live_neighbors))
     }
 
     pub fn toggle(&mut self, node: &Node) {
+        let node = match self.topology.wrap(node) {
+            Some(node) => node,
+            None => return,
+        };
         if !self.live_nodes.contains(&node) {
-            self.live_nodes.push(node.clone());
+            self.live_nodes.push(node);
         } else {
             if let Some(index) = self.live_nodes
                 .iter()
@@ -87,6 +129,7 @@ impl Game {
 #[cfg(test)]
 mod test {
     use crate::game::Game;
+    use crate::test_util::assert_same_nodes;
     use super::*;
 
     #[test]
@@ -113,7 +156,8 @@ mod test {
     #[test]
     fn game_with_live_nodes_returns_neighbor_boundary() {
         let mut game = Game {
-            live_nodes: vec![Node { x: 0, y: 0 }]
+            live_nodes: vec![Node { x: 0, y: 0 }],
+            ..Game::new()
         };
         let mut boundary = game.get_boundary();
         assert_eq!(boundary.lower, Node { x: -1, y: -1 });
@@ -130,6 +174,16 @@ mod test {
         assert_eq!(boundary.upper, Node { x: 3, y: 4 });
     }
 
+    #[test]
+    fn live_boundary_is_tight_around_live_nodes() {
+        let mut game = Game::new();
+        assert!(game.get_live_boundary().is_none());
+        game.live_nodes = vec![Node { x: 0, y: 0 }, Node { x: 2, y: 3 }, Node { x: -1, y: 1 }];
+        let boundary = game.get_live_boundary().unwrap();
+        assert_eq!(boundary.lower, Node { x: -1, y: 0 });
+        assert_eq!(boundary.upper, Node { x: 2, y: 3 });
+    }
+
     #[test]
     fn game_example_1_passes() {
         let mut game = Game {
@@ -138,7 +192,8 @@ mod test {
                 Node { x: 3, y: 1 },
                 Node { x: 4, y: 1 },
                 Node { x: 4, y: 2 },
-            ]
+            ],
+            ..Game::new()
         };
         game.evolve();
         assert_eq!(game.live_nodes.len(), 6);
@@ -153,7 +208,8 @@ mod test {
     #[test]
     fn a_live_node_with_0_live_neighbors_should_die() {
         let game = Game {
-            live_nodes: vec![Node { x: 0, y: 0 }]
+            live_nodes: vec![Node { x: 0, y: 0 }],
+            ..Game::new()
         };
         let live_neighbors = game.get_live_neighbors(&Node { x: 0, y: 0 });
         assert!(game.live_node_should_die(&live_neighbors));
@@ -162,7 +218,8 @@ mod test {
     #[test]
     fn a_live_node_with_1_live_neighbors_should_die() {
         let game = Game {
-            live_nodes: vec![Node { x: 0, y: 0 }, Node { x: 1, y: 1 }]
+            live_nodes: vec![Node { x: 0, y: 0 }, Node { x: 1, y: 1 }],
+            ..Game::new()
         };
         let live_neighbors = game.get_live_neighbors(&Node { x: 0, y: 0 });
         assert!(game.live_node_should_die(&live_neighbors));
@@ -171,7 +228,8 @@ mod test {
     #[test]
     fn a_live_node_with_2_live_neighbors_should_not_die() {
         let game = Game {
-            live_nodes: vec![Node { x: 0, y: 0 }, Node { x: 1, y: 1 }, Node { x: -1, y: 0 }]
+            live_nodes: vec![Node { x: 0, y: 0 }, Node { x: 1, y: 1 }, Node { x: -1, y: 0 }],
+            ..Game::new()
         };
 
         let live_neighbors = game.get_live_neighbors(&Node { x: 0, y: 0 });
@@ -185,7 +243,8 @@ mod test {
                 Node { x: 0, y: 0 },
                 Node { x: 1, y: 1 },
                 Node { x: -1, y: 0 },
-                Node { x: -1, y: -1 }]
+                Node { x: -1, y: -1 }],
+            ..Game::new()
         };
         let live_neighbors = game.get_live_neighbors(&Node { x: 0, y: 0 });
         assert!(!game.live_node_should_die(&live_neighbors));
@@ -199,7 +258,8 @@ mod test {
                 Node { x: 1, y: 1 },
                 Node { x: -1, y: 0 },
                 Node { x: 0, y: -1 },
-                Node { x: -1, y: -1 }]
+                Node { x: -1, y: -1 }],
+            ..Game::new()
         };
         let live_neighbors = game.get_live_neighbors(&Node { x: 0, y: 0 });
         assert!(game.live_node_should_die(&live_neighbors));
@@ -208,7 +268,8 @@ mod test {
     #[test]
     fn a_dead_cell_with_1_live_neighbours_should_not_live() {
         let game = Game {
-            live_nodes: vec![Node { x: 1, y: 1 }]
+            live_nodes: vec![Node { x: 1, y: 1 }],
+            ..Game::new()
         };
         let live_neighbors = game.get_live_neighbors(&Node { x: 0, y: 0 });
         assert!(!game.dead_node_should_live(&live_neighbors));
@@ -217,7 +278,8 @@ mod test {
     #[test]
     fn a_dead_cell_with_2_live_neighbours_should_not_live() {
         let game = Game {
-            live_nodes: vec![Node { x: 1, y: 1 }, Node { x: -1, y: -1 }]
+            live_nodes: vec![Node { x: 1, y: 1 }, Node { x: -1, y: -1 }],
+            ..Game::new()
         };
         let live_neighbors = game.get_live_neighbors(&Node { x: 0, y: 0 });
         assert!(!game.dead_node_should_live(&live_neighbors));
@@ -226,7 +288,8 @@ mod test {
     #[test]
     fn a_dead_cell_with_3_live_neighbours_should_live() {
         let game = Game {
-            live_nodes: vec![Node { x: 1, y: 1 }, Node { x: -1, y: -1 }, Node { x: 1, y: -1 }]
+            live_nodes: vec![Node { x: 1, y: 1 }, Node { x: -1, y: -1 }, Node { x: 1, y: -1 }],
+            ..Game::new()
         };
         let live_neighbors = game.get_live_neighbors(&Node { x: 0, y: 0 });
         assert!(game.dead_node_should_live(&live_neighbors));
@@ -239,7 +302,8 @@ mod test {
                 Node { x: 1, y: 1 },
                 Node { x: -1, y: -1 },
                 Node { x: 1, y: -1 },
-                Node { x: 0, y: 1 }]
+                Node { x: 0, y: 1 }],
+            ..Game::new()
         };
         let live_neighbors = game.get_live_neighbors(&Node { x: 0, y: 0 });
         assert!(!game.dead_node_should_live(&live_neighbors));
@@ -256,7 +320,8 @@ mod test {
     #[test]
     fn game_with_live_nodes_near_zero_node_returns_live_neighbors() {
         let game = Game {
-            live_nodes: vec![Node { x: -1, y: -1 }, Node { x: 1, y: 1 }]
+            live_nodes: vec![Node { x: -1, y: -1 }, Node { x: 1, y: 1 }],
+            ..Game::new()
         };
         let node_zero = Node { x: 0, y: 0 };
         let live_neighbors = game.get_live_neighbors(&node_zero);
@@ -268,7 +333,8 @@ mod test {
     #[test]
     fn game_with_live_nodes_not_near_zero_node_returns_0_live_neighbors() {
         let game = Game {
-            live_nodes: vec![Node { x: -3, y: -3 }, Node { x: -3, y: 3 }]
+            live_nodes: vec![Node { x: -3, y: -3 }, Node { x: -3, y: 3 }],
+            ..Game::new()
         };
         let node_zero = Node { x: 0, y: 0 };
         let live_neighbors = game.get_live_neighbors(&node_zero);
@@ -278,7 +344,8 @@ mod test {
     #[test]
     fn can_toggle_node_from_dead_to_alive() {
         let mut game = Game {
-            live_nodes: vec![]
+            live_nodes: vec![],
+            ..Game::new()
         };
         let node_zero = Node { x: 0, y: 0 };
         game.toggle(&node_zero);
@@ -288,10 +355,193 @@ mod test {
     #[test]
     fn can_toggle_node_from_alive_to_dead() {
         let mut game = Game {
-            live_nodes: vec![Node { x: 0, y: 0 }]
+            live_nodes: vec![Node { x: 0, y: 0 }],
+            ..Game::new()
         };
         let node_zero = Node { x: 0, y: 0 };
         game.toggle(&node_zero);
         assert_eq!(game.live_nodes.len(), 0);
     }
-}
\ No newline at end of file
+
+    fn glider() -> Vec<Node> {
+        vec![
+            Node { x: 1, y: 2 },
+            Node { x: 2, y: 1 },
+            Node { x: 0, y: 0 },
+            Node { x: 1, y: 0 },
+            Node { x: 2, y: 0 },
+        ]
+    }
+
+    #[test]
+    fn torus_game_with_live_nodes_near_edge_returns_whole_grid_boundary() {
+        let mut game = Game::with_topology(Topology::Torus { width: 8, height: 6 }).unwrap();
+        game.live_nodes = vec![Node { x: 0, y: 0 }];
+        let boundary = game.get_boundary();
+        assert_eq!(boundary.lower, Node { x: -1, y: -1 });
+        assert_eq!(boundary.upper, Node { x: 1, y: 1 });
+
+        game.live_nodes = vec![Node { x: 3, y: 0 }];
+        let boundary = game.get_boundary();
+        assert_eq!(boundary.lower, Node { x: -4, y: -3 });
+        assert_eq!(boundary.upper, Node { x: 3, y: 2 });
+    }
+
+    #[test]
+    fn torus_game_counts_live_neighbors_across_edges() {
+        let game = Game {
+            live_nodes: vec![Node { x: 3, y: 2 }, Node { x: -4, y: -3 }],
+            topology: Topology::Torus { width: 8, height: 6 },
+            ..Game::new()
+        };
+        let live_neighbors = game.get_live_neighbors(&Node { x: -4, y: 2 });
+        assert_eq!(live_neighbors.len(), 2);
+    }
+
+    #[test]
+    fn toggle_on_torus_wraps_node_into_grid() {
+        let mut game = Game::with_topology(Topology::Torus { width: 8, height: 6 }).unwrap();
+        game.toggle(&Node { x: 4, y: 3 });
+        assert_eq!(game.live_nodes, vec![Node { x: -4, y: -3 }]);
+    }
+
+    fn translate(nodes: Vec<Node>, dx: Coord, dy: Coord) -> Vec<Node> {
+        nodes.into_iter().map(|node| Node { x: node.x + dx, y: node.y + dy }).collect()
+    }
+
+    fn evolve_glider_on(topology: Topology, start: Vec<Node>, generations: usize) -> Vec<Node> {
+        let mut game = Game { live_nodes: start, topology, ..Game::new() };
+        for _ in 0..generations {
+            game.evolve();
+        }
+        game.live_nodes
+    }
+
+    #[test]
+    fn glider_re_enters_torus_on_opposite_side() {
+        let mut game = Game {
+            live_nodes: glider(),
+            topology: Topology::Torus { width: 8, height: 8 },
+            ..Game::new()
+        };
+        for _ in 0..16 {
+            game.evolve();
+        }
+        assert_eq!(game.live_nodes.len(), 5);
+        assert!(game.live_nodes.iter().any(|node| node.x == -4));
+        for _ in 0..16 {
+            game.evolve();
+        }
+        assert_eq!(game.live_nodes.len(), 5);
+        for node in glider() {
+            assert!(game.live_nodes.contains(&node));
+        }
+    }
+
+    #[test]
+    fn glider_crossing_torus_edge_comes_back_unchanged() {
+        let nodes = evolve_glider_on("T32,8".parse().unwrap(), glider(), 32);
+        assert_same_nodes(&nodes, &translate(glider(), 8, 0));
+    }
+
+    #[test]
+    fn glider_crossing_shifted_torus_edge_comes_back_shifted() {
+        let nodes = evolve_glider_on("T32+3,8".parse().unwrap(), glider(), 32);
+        assert_same_nodes(&nodes, &translate(glider(), 5, 0));
+    }
+
+    #[test]
+    fn glider_crossing_klein_bottle_twisted_edge_comes_back_mirrored() {
+        let nodes = evolve_glider_on("K32*,8".parse().unwrap(), glider(), 32);
+        let mirrored: Vec<Node> = glider().into_iter().map(|node| Node { x: -9 - node.x, y: node.y }).collect();
+        assert_same_nodes(&nodes, &mirrored);
+    }
+
+    #[test]
+    fn glider_crossing_klein_bottle_plain_edge_comes_back_unchanged() {
+        let nodes = evolve_glider_on("K8*,32".parse().unwrap(), glider(), 32);
+        assert_same_nodes(&nodes, &translate(glider(), 0, -8));
+    }
+
+    #[test]
+    fn glider_crossing_cross_surface_edges_comes_back_mirrored() {
+        let nodes = evolve_glider_on("C32,8".parse().unwrap(), glider(), 32);
+        let mirrored: Vec<Node> = glider().into_iter().map(|node| Node { x: -9 - node.x, y: node.y }).collect();
+        assert_same_nodes(&nodes, &mirrored);
+
+        let nodes = evolve_glider_on("C8,32".parse().unwrap(), glider(), 32);
+        let mirrored: Vec<Node> = glider().into_iter().map(|node| Node { x: node.x, y: 7 - node.y }).collect();
+        assert_same_nodes(&nodes, &mirrored);
+    }
+
+    #[test]
+    fn glider_crossing_sphere_bottom_edge_comes_out_of_right_edge_rotated() {
+        let start = translate(glider(), -6, -7);
+        let nodes = evolve_glider_on(Topology::Sphere { size: 16 }, start.clone(), 32);
+        let rotated: Vec<Node> = start.into_iter().map(|node| Node { x: node.y + 8, y: -9 - node.x }).collect();
+        assert_same_nodes(&nodes, &rotated);
+    }
+
+    #[test]
+    fn glider_evolves_beyond_i32_range() {
+        let start = translate(glider(), 3_000_000_000, -3_000_000_000);
+        let mut game = Game { live_nodes: start.clone(), ..Game::new() };
+        for _ in 0..4 {
+            game.evolve();
+        }
+        assert_same_nodes(&game.live_nodes, &translate(start, 1, -1));
+    }
+
+    #[test]
+    fn boundary_at_the_edge_of_the_universe_saturates() {
+        let game = Game {
+            live_nodes: vec![Node { x: Coord::MAX, y: Coord::MIN }],
+            ..Game::new()
+        };
+        let boundary = game.get_boundary();
+        assert_eq!(boundary.lower, Node { x: Coord::MAX - 1, y: Coord::MIN });
+        assert_eq!(boundary.upper, Node { x: Coord::MAX, y: Coord::MIN + 1 });
+    }
+
+    #[test]
+    fn block_in_the_corner_of_the_universe_is_still() {
+        let block = vec![
+            Node { x: Coord::MAX - 1, y: Coord::MAX - 1 },
+            Node { x: Coord::MAX, y: Coord::MAX - 1 },
+            Node { x: Coord::MAX - 1, y: Coord::MAX },
+            Node { x: Coord::MAX, y: Coord::MAX },
+        ];
+        let mut game = Game { live_nodes: block.clone(), ..Game::new() };
+        game.evolve();
+        assert_same_nodes(&game.live_nodes, &block);
+    }
+
+    #[test]
+    fn glider_hitting_the_edge_of_the_universe_does_not_overflow() {
+        let mut game = Game {
+            live_nodes: translate(glider(), Coord::MAX - 4, Coord::MIN + 2),
+            ..Game::new()
+        };
+        for _ in 0..32 {
+            game.evolve();
+        }
+        assert!(game.live_nodes.len() <= 5);
+    }
+
+    #[test]
+    fn game_follows_its_rule() {
+        let mut game = Game::with_rule("B36/S23".parse().unwrap());
+        game.live_nodes = vec![
+            Node { x: -1, y: 1 },
+            Node { x: 1, y: 1 },
+            Node { x: -1, y: 0 },
+            Node { x: -1, y: -1 },
+            Node { x: 0, y: -1 },
+            Node { x: 1, y: -1 },
+        ];
+        let live_neighbors = game.get_live_neighbors(&Node { x: 0, y: 0 });
+        assert!(game.dead_node_should_live(&live_neighbors));
+        game.rule = Rule::conway();
+        assert!(!game.dead_node_should_live(&live_neighbors));
+    }
+}
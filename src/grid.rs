@@ -1,17 +1,45 @@
-use crate::node::Node;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
+use crate::node::{Coord, Node};
 
+/// The largest radius a saved grid is read with, since the grid holds
+/// (2 * radius)^2 nodes.
+pub const MAX_RADIUS: i32 = 2048;
+
+#[derive(Serialize)]
 pub struct Grid {
     pub radius: i32,
     pub cell_size: i32,
+    #[serde(skip)]
     cells: Vec<Node>,
 }
 
+/// The part of a grid that is saved; the cells are rebuilt from the radius.
+#[derive(Deserialize)]
+struct GridSize {
+    radius: i32,
+    cell_size: i32,
+}
+
+impl<'de> Deserialize<'de> for Grid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let size = GridSize::deserialize(deserializer)?;
+        if !(0..=MAX_RADIUS).contains(&size.radius) {
+            return Err(D::Error::custom(format!("grid radius {} is not between 0 and {}", size.radius, MAX_RADIUS)));
+        }
+        if size.cell_size < 1 {
+            return Err(D::Error::custom(format!("grid cell size {} is not positive", size.cell_size)));
+        }
+        Ok(Grid::new(size.radius, size.cell_size))
+    }
+}
+
 impl Grid {
     pub fn new(radius: i32, cell_size: i32) -> Self {
         let mut cells = Vec::new();
         for y in -radius..radius {
             for x in -radius..radius {
-                cells.push(Node { x, y });
+                cells.push(Node { x: x as Coord, y: y as Coord });
             }
         }
         Self {
@@ -26,7 +54,8 @@ impl Grid {
     }
 
     pub fn get_index(&self, node: &Node) -> usize {
-        (((node.y + self.radius) * (self.radius * 2)) + (node.x + self.radius)) as usize
+        let radius = self.radius as Coord;
+        (((node.y + radius) * (radius * 2)) + (node.x + radius)) as usize
     }
 
     pub fn get_cells(&self) -> &Vec<Node> {
@@ -36,8 +65,8 @@ impl Grid {
     pub fn get_node_from_world_pos(world_x: f32, world_y: f32, cell_size: u32) -> Node {
         let offset = (cell_size / 2) as f32;
         Node {
-            x: { ((world_x + offset) / cell_size as f32).floor() as i32 },
-            y: { ((world_y + offset) / cell_size as f32).floor() as i32 },
+            x: { ((world_x + offset) / cell_size as f32).floor() as Coord },
+            y: { ((world_y + offset) / cell_size as f32).floor() as Coord },
         }
     }
 }
@@ -98,4 +127,13 @@ mod test {
         // 1, 1
         assert_eq!(Grid::get_node_from_world_pos(world_x, world_y, cell_size), Node { x: 1, y: 1 });
     }
+
+    #[test]
+    fn rejects_saved_radii_out_of_bounds() {
+        let grid: Grid = serde_json::from_str(r#"{"radius": 2, "cell_size": 10}"#).unwrap();
+        assert_eq!(grid.get_cells().len(), 16);
+        assert!(serde_json::from_str::<Grid>(r#"{"radius": -1, "cell_size": 10}"#).is_err());
+        assert!(serde_json::from_str::<Grid>(r#"{"radius": 2147483647, "cell_size": 10}"#).is_err());
+        assert!(serde_json::from_str::<Grid>(r#"{"radius": 2, "cell_size": 0}"#).is_err());
+    }
 }
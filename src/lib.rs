@@ -0,0 +1,21 @@
+pub mod node;
+pub mod game;
+pub mod box_boundary;
+pub mod grid;
+pub mod topology;
+pub mod rule;
+pub mod format;
+pub mod quadtree;
+pub mod apgcode;
+pub mod canonical;
+pub mod catalog;
+pub mod render;
+pub mod session;
+pub mod census;
+pub mod period;
+pub mod object;
+pub mod stats;
+#[cfg(feature = "scripting")]
+pub mod script;
+#[cfg(test)]
+mod test_util;
@@ -1,48 +1,149 @@
+use std::path::PathBuf;
 use std::time::Duration;
-use crate::game::Game;
-use crate::grid::Grid;
+use game_of_life::box_boundary::BoxBoundary;
+use game_of_life::game::Game;
+use game_of_life::grid::Grid;
 use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use bevy::render::camera::RenderTarget;
 use bevy::sprite::Mesh2dHandle;
-use crate::node::Node;
-
-mod node;
-mod game;
-mod box_boundary;
-mod grid;
+use game_of_life::node::{Coord, Node};
+#[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+use game_of_life::script::{run_script, ScriptState};
+use game_of_life::session::{GameState, GameStatus, Session, View};
+use game_of_life::stats::Statistics;
+use game_of_life::topology::Topology;
 
 fn main() {
-    App::new()
-        .insert_resource(GameState {
-            game: Game::new(),
-            grid: Grid::new(1000, 10),
-            status: GameStatus::Pause,
+    let session_path = get_arg("--session");
+    let session = match &session_path {
+        Some(path) if PathBuf::from(path).exists() => Session::load(path).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }),
+        _ => Session::new(Game::with_topology(get_topology_arg()).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        })),
+    };
+    let mut stats_file = StatsFile {
+        path: get_arg("--stats").unwrap_or_else(|| "stats.csv".to_string()).into(),
+        statistics: Statistics::new(),
+    };
+    stats_file.restart(&session.state);
+    let mut app = App::new();
+    app
+        .insert_resource(stats_file)
+        .insert_resource(SessionFile {
+            path: session_path.unwrap_or_else(|| "session.json".to_string()).into(),
+            view: session.view,
         })
+        .insert_resource(EvolutionTimer {
+            timer: Timer::new(Duration::from_millis(session.speed), TimerMode::Repeating),
+        })
+        .insert_resource(session.state)
+        .init_resource::<Selection>()
+        .init_resource::<Paste>()
         .insert_resource(ClearColor(Color::rgb(0., 0., 0.)))
         .add_plugins(DefaultPlugins)
         .add_plugin(LogDiagnosticsPlugin::default())
         .add_plugin(FrameTimeDiagnosticsPlugin::default())
         .add_startup_system(setup_game)
+        .add_startup_system(setup_topology_edges)
+        .add_startup_system(setup_labels)
         .add_system(my_cursor_system)
         .add_system(my_game_play_pause_system)
         .add_system(evolve_game)
-        .run();
+        .add_system(session_system)
+        .add_system(stats_system)
+        .add_system(selection_system)
+        .add_system(paste_preview_system)
+        .add_system(label_system);
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_system(clipboard_system);
+    #[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+    app
+        .insert_resource(ScriptFile {
+            path: get_arg("--script").unwrap_or_else(|| "script.rhai".to_string()).into(),
+        })
+        .add_system(script_system);
+    app.run();
+}
+
+/// The nodes between where the right mouse button was pressed and where it is
+/// or was released.
+#[derive(Resource, Default)]
+struct Selection {
+    start: Option<Node>,
+    end: Option<Node>,
+}
+
+impl Selection {
+    fn get_boundary(&self) -> Option<BoxBoundary> {
+        let (start, end) = (self.start.as_ref()?, self.end.as_ref()?);
+        Some(BoxBoundary {
+            lower: Node { x: start.x.min(end.x), y: start.y.min(end.y) },
+            upper: Node { x: start.x.max(end.x), y: start.y.max(end.y) },
+        })
+    }
+}
+
+/// A pasted pattern waiting to be placed, its top left node at the origin.
+#[derive(Resource, Default)]
+struct Paste {
+    pattern: Option<Game>,
+}
+
+#[derive(Component)]
+struct SelectionBox;
+
+#[derive(Component)]
+struct PastePreview;
+
+/// Where Ctrl+S saves the session and Ctrl+L loads it from, and the view the
+/// camera starts with.
+#[derive(Resource)]
+struct SessionFile {
+    path: PathBuf,
+    view: View,
+}
+
+/// The Rhai script Ctrl+R runs.
+#[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+#[derive(Resource)]
+struct ScriptFile {
+    path: PathBuf,
+}
+
+/// The statistics of every generation shown so far, and where Ctrl+E exports
+/// them to as CSV or JSON.
+#[derive(Resource)]
+struct StatsFile {
+    path: PathBuf,
+    statistics: Statistics,
 }
 
-#[derive(PartialEq)]
-enum GameStatus {
-    Play,
-    Pause,
+impl StatsFile {
+    /// Starts the statistics again from the game as it is, after it changed
+    /// other than by evolving, so no record counts the edit as births and
+    /// deaths.
+    fn restart(&mut self, game_state: &GameState) {
+        self.statistics = Statistics::new();
+        self.statistics.record(game_state.generation, &game_state.game);
+    }
 }
 
+/// Whether K has turned on the names of known objects, and the font they're
+/// drawn with.
 #[derive(Resource)]
-struct GameState {
-    game: Game,
-    status: GameStatus,
-    grid: Grid,
+struct Labels {
+    visible: bool,
+    font: Handle<Font>,
 }
 
+#[derive(Component)]
+struct ObjectLabel;
+
 #[derive(Resource)]
 struct EvolutionTimer {
     timer: Timer,
@@ -53,8 +154,8 @@ struct MainCamera;
 
 #[derive(Component)]
 struct Cell {
-    x: i32,
-    y: i32,
+    x: Coord,
+    y: Coord,
 }
 
 #[derive(Bundle)]
@@ -72,8 +173,8 @@ impl CellBundle {
                 material,
                 transform: Transform::from_translation(
                     Vec3::new(
-                        (node.x * cell_size) as f32,
-                        (node.y * cell_size) as f32, 0.)
+                        node.x as f32 * cell_size as f32,
+                        node.y as f32 * cell_size as f32, 0.)
                 ),
                 ..default()
             },
@@ -90,8 +191,19 @@ fn setup_game(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     game_state: Res<GameState>,
+    session_file: Res<SessionFile>,
 ) {
-    commands.spawn((Camera2dBundle::default(), MainCamera));
+    let mut camera = Camera2dBundle::default();
+    apply_view(&session_file.view, &mut camera.transform, &mut camera.projection);
+    commands.spawn((camera, MainCamera));
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite { color: Color::rgba(1., 1., 1., 0.2), ..default() },
+            visibility: Visibility { is_visible: false },
+            ..default()
+        },
+        SelectionBox,
+    ));
     for node in game_state.grid.get_cells() {
         if game_state.game.is_node_alive(node.x, node.y) {
             let mesh = meshes.add(shape::Cube::new(game_state.grid.cell_size as f32).into()).into();
@@ -99,15 +211,106 @@ fn setup_game(
             commands.spawn(CellBundle::new(mesh, material, node, game_state.grid.cell_size));
         }
     }
-    commands.insert_resource(EvolutionTimer {
-        timer: Timer::new(Duration::from_millis(500), TimerMode::Repeating),
-    });
+}
+
+fn setup_topology_edges(mut commands: Commands, game_state: Res<GameState>) {
+    let bounds = match game_state.game.topology.get_bounds() {
+        Some(bounds) => bounds,
+        None => return,
+    };
+    let cell_size = game_state.grid.cell_size as f32;
+    let left = (bounds.lower.x as f32 - 0.5) * cell_size;
+    let right = (bounds.upper.x as f32 + 0.5) * cell_size;
+    let bottom = (bounds.lower.y as f32 - 0.5) * cell_size;
+    let top = (bounds.upper.y as f32 + 0.5) * cell_size;
+    let edges = [
+        (Vec2::new(left, bottom), Vec2::new(right, bottom)),
+        (Vec2::new(left, top), Vec2::new(right, top)),
+        (Vec2::new(left, bottom), Vec2::new(left, top)),
+        (Vec2::new(right, bottom), Vec2::new(right, top)),
+    ];
+    for (start, end) in edges {
+        let size = (end - start).abs() + Vec2::splat(2.);
+        commands.spawn(SpriteBundle {
+            sprite: Sprite {
+                color: Color::GRAY,
+                custom_size: Some(size),
+                ..default()
+            },
+            transform: Transform::from_translation(((start + end) / 2.).extend(1.)),
+            ..default()
+        });
+    }
+}
+
+fn setup_labels(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = get_arg("--font").unwrap_or_else(|| "fonts/DejaVuSansMono.ttf".to_string());
+    commands.insert_resource(Labels { visible: false, font: asset_server.load(font) });
+}
+
+/// Toggles the labels with K and puts the catalog name right of every known
+/// object whenever the game changes.
+fn label_system(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    game_state: Res<GameState>,
+    mut labels: ResMut<Labels>,
+    label_q: Query<Entity, With<ObjectLabel>>,
+) {
+    let toggled = keys.just_pressed(KeyCode::K);
+    if toggled {
+        labels.visible = !labels.visible;
+    }
+    if !toggled && !(labels.visible && game_state.is_changed()) {
+        return;
+    }
+    for entity in label_q.iter() {
+        commands.entity(entity).despawn();
+    }
+    if !labels.visible {
+        return;
+    }
+    let cell_size = game_state.grid.cell_size as f32;
+    let style = TextStyle { font: labels.font.clone(), font_size: 14., color: Color::WHITE };
+    for recognized in game_state.game.recognize_objects() {
+        let (name, (x, y)) = match (recognized.name, recognized.get_label_position()) {
+            (Some(name), Some(position)) => (name, position),
+            _ => continue,
+        };
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(name, style.clone()).with_alignment(TextAlignment::CENTER_LEFT),
+                transform: Transform::from_xyz(x as f32 * cell_size, y as f32 * cell_size, 2.),
+                ..default()
+            },
+            ObjectLabel,
+        ));
+    }
+}
+
+fn get_arg(name: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|index| args.get(index + 1))
+        .cloned()
+}
+
+fn get_topology_arg() -> Topology {
+    match get_arg("--topology") {
+        Some(topology) => topology.parse().unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            Topology::Plane
+        }),
+        None => Topology::Plane,
+    }
 }
 
 fn evolve_game(
     mut commands: Commands,
     mut game_state: ResMut<GameState>,
     mut config: ResMut<EvolutionTimer>,
+    mut stats_file: ResMut<StatsFile>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
     cell_q: Query<Entity, With<Cell>>,
@@ -116,16 +319,30 @@ fn evolve_game(
     if game_state.status == GameStatus::Pause { return; };
     config.timer.tick(time.delta());
     if !config.timer.finished() { return; }
+    game_state.game.evolve();
+    game_state.generation += 1;
+    stats_file.statistics.record(game_state.generation, &game_state.game);
+    spawn_cells(&mut commands, &mut materials, &mut meshes, &cell_q, &game_state);
+}
+
+/// Replaces the drawn cells with the live nodes inside the grid.
+fn spawn_cells(
+    commands: &mut Commands,
+    materials: &mut Assets<ColorMaterial>,
+    meshes: &mut Assets<Mesh>,
+    cell_q: &Query<Entity, With<Cell>>,
+    game_state: &GameState,
+) {
     for cell in cell_q.iter() {
         let mut entity = commands.entity(cell);
         entity.despawn();
     }
-    game_state.game.evolve();
     for node in &game_state.game.live_nodes {
-        if node.x < -game_state.grid.radius ||
-            node.x >= game_state.grid.radius ||
-            node.y < -game_state.grid.radius ||
-            node.y >= game_state.grid.radius { continue; };
+        let radius = game_state.grid.radius as Coord;
+        if node.x < -radius ||
+            node.x >= radius ||
+            node.y < -radius ||
+            node.y >= radius { continue; };
         if game_state.game.is_node_alive(node.x, node.y) {
             let mesh = meshes.add(shape::Cube::new(game_state.grid.cell_size as f32).into()).into();
             let material = materials.add(ColorMaterial::from(Color::BLUE));
@@ -134,6 +351,103 @@ fn evolve_game(
     }
 }
 
+fn apply_view(view: &View, transform: &mut Transform, projection: &mut OrthographicProjection) {
+    transform.translation.x = view.x;
+    transform.translation.y = view.y;
+    projection.scale = view.zoom;
+}
+
+fn session_system(
+    keys: Res<Input<KeyCode>>,
+    session_file: Res<SessionFile>,
+    mut game_state: ResMut<GameState>,
+    mut config: ResMut<EvolutionTimer>,
+    mut stats_file: ResMut<StatsFile>,
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    cell_q: Query<Entity, With<Cell>>,
+) {
+    let control = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    let (mut transform, mut projection) = q_camera.single_mut();
+    if control && keys.just_pressed(KeyCode::S) {
+        let view = View { x: transform.translation.x, y: transform.translation.y, zoom: projection.scale };
+        let speed = config.timer.duration().as_millis() as u64;
+        if let Err(error) = game_state.save(&view, speed, &session_file.path) {
+            eprintln!("{}", error);
+        }
+    } else if control && keys.just_pressed(KeyCode::L) {
+        match Session::load(&session_file.path) {
+            Ok(session) => {
+                *game_state = session.state;
+                stats_file.restart(&game_state);
+                config.timer.set_duration(Duration::from_millis(session.speed));
+                apply_view(&session.view, &mut transform, &mut projection);
+                spawn_cells(&mut commands, &mut materials, &mut meshes, &cell_q, &game_state);
+            }
+            Err(error) => eprintln!("{}", error),
+        }
+    }
+}
+
+/// Ctrl+R runs the script on the game and moves the camera where it left it.
+#[cfg(all(feature = "scripting", not(target_arch = "wasm32")))]
+fn script_system(
+    keys: Res<Input<KeyCode>>,
+    script_file: Res<ScriptFile>,
+    mut game_state: ResMut<GameState>,
+    mut stats_file: ResMut<StatsFile>,
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    cell_q: Query<Entity, With<Cell>>,
+) {
+    let control = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if !control || !keys.just_pressed(KeyCode::R) { return; }
+    let script = match std::fs::read_to_string(&script_file.path) {
+        Ok(script) => script,
+        Err(error) => {
+            eprintln!("{}: {}", script_file.path.display(), error);
+            return;
+        }
+    };
+    let (mut transform, mut projection) = q_camera.single_mut();
+    let cell_size = game_state.grid.cell_size as f32;
+    let state = ScriptState {
+        game: game_state.game.clone(),
+        generation: game_state.generation,
+        camera_x: (transform.translation.x / cell_size) as f64,
+        camera_y: (transform.translation.y / cell_size) as f64,
+        zoom: projection.scale as f64,
+    };
+    match run_script(&script, state) {
+        Ok(state) => {
+            game_state.game = state.game;
+            game_state.generation = state.generation;
+            stats_file.restart(&game_state);
+            let view = View {
+                x: state.camera_x as f32 * cell_size,
+                y: state.camera_y as f32 * cell_size,
+                zoom: state.zoom as f32,
+            };
+            apply_view(&view, &mut transform, &mut projection);
+            spawn_cells(&mut commands, &mut materials, &mut meshes, &cell_q, &game_state);
+        }
+        Err(error) => eprintln!("{}: {}", script_file.path.display(), error),
+    }
+}
+
+fn stats_system(keys: Res<Input<KeyCode>>, stats_file: Res<StatsFile>) {
+    let control = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if control && keys.just_pressed(KeyCode::E) {
+        if let Err(error) = stats_file.statistics.save(&stats_file.path) {
+            eprintln!("{}", error);
+        }
+    }
+}
+
 fn my_game_play_pause_system(
     mut game_state: ResMut<GameState>,
     keys: Res<Input<KeyCode>>,
@@ -152,18 +466,48 @@ fn my_cursor_system(
     q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
     buttons: Res<Input<MouseButton>>,
     mut game_state: ResMut<GameState>,
+    mut stats_file: ResMut<StatsFile>,
     mut commands: Commands,
     mut materials: ResMut<Assets<ColorMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
     cell_q: Query<(&Cell, Entity)>,
+    mut paste: ResMut<Paste>,
+    preview_q: Query<Entity, With<PastePreview>>,
 ) {
     if !buttons.just_pressed(MouseButton::Left) {
         return;
     }
-    let world_pos_res = get_cursor_world_pos(windows, q_camera);
-    if world_pos_res.is_none() { return; }
-    let world_pos = world_pos_res.unwrap();
-    let node = Grid::get_node_from_world_pos(world_pos.x, world_pos.y, game_state.grid.cell_size as u32);
+    let node = match get_cursor_node(windows, q_camera, &game_state.grid) {
+        Some(node) => node,
+        None => return,
+    };
+    if let Some(pattern) = paste.pattern.take() {
+        for entity in preview_q.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        for offset in &pattern.live_nodes {
+            let node = match (node.x.checked_add(offset.x), node.y.checked_add(offset.y)) {
+                (Some(x), Some(y)) => Node { x, y },
+                _ => continue,
+            };
+            let node = match game_state.game.topology.wrap(&node) {
+                Some(node) => node,
+                None => continue,
+            };
+            if !game_state.game.is_node_alive(node.x, node.y) {
+                let mesh = meshes.add(shape::Cube::new(game_state.grid.cell_size as f32).into()).into();
+                let material = materials.add(ColorMaterial::from(Color::BLUE));
+                commands.spawn(CellBundle::new(mesh, material, &node, game_state.grid.cell_size));
+                game_state.game.toggle(&node);
+            }
+        }
+        stats_file.restart(&game_state);
+        return;
+    }
+    let node = match game_state.game.topology.wrap(&node) {
+        Some(node) => node,
+        None => return,
+    };
     if game_state.game.is_node_alive(node.x, node.y) {
         for (cell, entity) in cell_q.iter() {
             if node.x == cell.x && node.y == cell.y {
@@ -176,6 +520,153 @@ fn my_cursor_system(
         commands.spawn(CellBundle::new(mesh, material, &node, game_state.grid.cell_size));
     }
     game_state.game.toggle(&node);
+    stats_file.restart(&game_state);
+}
+
+fn selection_system(
+    windows: Res<Windows>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    buttons: Res<Input<MouseButton>>,
+    keys: Res<Input<KeyCode>>,
+    game_state: Res<GameState>,
+    mut selection: ResMut<Selection>,
+    mut box_q: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<SelectionBox>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        *selection = Selection::default();
+    }
+    if buttons.pressed(MouseButton::Right) {
+        if let Some(node) = get_cursor_node(windows, q_camera, &game_state.grid) {
+            if buttons.just_pressed(MouseButton::Right) {
+                selection.start = Some(node.clone());
+            }
+            selection.end = Some(node);
+        }
+    }
+    let (mut transform, mut sprite, mut visibility) = box_q.single_mut();
+    let boundary = match selection.get_boundary() {
+        Some(boundary) => boundary,
+        None => {
+            visibility.is_visible = false;
+            return;
+        }
+    };
+    let cell_size = game_state.grid.cell_size as f32;
+    let (left, right) = (boundary.lower.x as f32 - 0.5, boundary.upper.x as f32 + 0.5);
+    let (bottom, top) = (boundary.lower.y as f32 - 0.5, boundary.upper.y as f32 + 0.5);
+    sprite.custom_size = Some(Vec2::new(right - left, top - bottom) * cell_size);
+    transform.translation = Vec3::new((left + right) / 2. * cell_size, (bottom + top) / 2. * cell_size, 2.);
+    visibility.is_visible = true;
+}
+
+/// Ctrl+C copies the selected nodes as RLE, Ctrl+V picks up the RLE or
+/// plaintext pattern on the clipboard to be placed with a left click.
+#[cfg(not(target_arch = "wasm32"))]
+fn clipboard_system(
+    keys: Res<Input<KeyCode>>,
+    selection: Res<Selection>,
+    game_state: Res<GameState>,
+    mut paste: ResMut<Paste>,
+    mut commands: Commands,
+    preview_q: Query<Entity, With<PastePreview>>,
+) {
+    if !keys.any_pressed([KeyCode::LControl, KeyCode::RControl]) {
+        return;
+    }
+    if keys.just_pressed(KeyCode::C) {
+        let boundary = match selection.get_boundary() {
+            Some(boundary) => boundary,
+            None => return,
+        };
+        let mut copy = Game::with_rule(game_state.game.rule.clone());
+        copy.live_nodes = game_state.game.live_nodes.iter()
+            .filter(|node| boundary.contains(node))
+            .cloned()
+            .collect();
+        let text = match copy.to_rle() {
+            Ok(text) => text,
+            Err(error) => {
+                eprintln!("{}", error);
+                return;
+            }
+        };
+        if let Err(error) = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+            eprintln!("{}", error);
+        }
+    } else if keys.just_pressed(KeyCode::V) {
+        let text = match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.get_text()) {
+            Ok(text) => text,
+            Err(error) => {
+                eprintln!("{}", error);
+                return;
+            }
+        };
+        let mut pattern = match Game::from_pattern(&text) {
+            Ok(pattern) => pattern,
+            Err(error) => {
+                eprintln!("{}", error);
+                return;
+            }
+        };
+        let boundary = match pattern.get_live_boundary() {
+            Some(boundary) => boundary,
+            None => return,
+        };
+        for node in pattern.live_nodes.iter_mut() {
+            *node = Node { x: node.x - boundary.lower.x, y: node.y - boundary.upper.y };
+        }
+        for entity in preview_q.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        let cell_size = game_state.grid.cell_size as f32;
+        commands.spawn((SpatialBundle::default(), PastePreview)).with_children(|parent| {
+            for node in &pattern.live_nodes {
+                parent.spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgba(0., 0., 1., 0.4),
+                        custom_size: Some(Vec2::splat(cell_size)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(node.x as f32 * cell_size, node.y as f32 * cell_size, 0.),
+                    ..default()
+                });
+            }
+        });
+        paste.pattern = Some(pattern);
+    }
+}
+
+/// Keeps the pasted pattern under the cursor until it is placed or Escape
+/// drops it.
+fn paste_preview_system(
+    windows: Res<Windows>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    keys: Res<Input<KeyCode>>,
+    game_state: Res<GameState>,
+    mut paste: ResMut<Paste>,
+    mut commands: Commands,
+    mut preview_q: Query<(Entity, &mut Transform), With<PastePreview>>,
+) {
+    if keys.just_pressed(KeyCode::Escape) {
+        paste.pattern = None;
+        for (entity, _) in preview_q.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+    let node = match get_cursor_node(windows, q_camera, &game_state.grid) {
+        Some(node) => node,
+        None => return,
+    };
+    let cell_size = game_state.grid.cell_size as f32;
+    for (_, mut transform) in preview_q.iter_mut() {
+        transform.translation = Vec3::new(node.x as f32 * cell_size, node.y as f32 * cell_size, 3.);
+    }
+}
+
+fn get_cursor_node(windows: Res<Windows>, q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>, grid: &Grid) -> Option<Node> {
+    let world_pos = get_cursor_world_pos(windows, q_camera)?;
+    Some(Grid::get_node_from_world_pos(world_pos.x, world_pos.y, grid.cell_size as u32))
 }
 
 fn get_cursor_world_pos(windows: Res<Windows>, q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>) -> Option<Vec2> {
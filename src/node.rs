@@ -1,7 +1,47 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+use crate::topology::Topology;
+
+/// The integer type of a node coordinate, 64 bits wide by default and 128 bits
+/// wide with the `wide-coordinates` feature. There is no arbitrary precision
+/// option: the universe ends at the type's extremes, and nodes past `Coord::MIN`
+/// or `Coord::MAX` don't exist and count as dead.
+#[cfg(not(feature = "wide-coordinates"))]
+pub type Coord = i64;
+#[cfg(feature = "wide-coordinates")]
+pub type Coord = i128;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Node {
-    pub(crate) x: i32,
-    pub(crate) y: i32,
+    #[cfg_attr(feature = "wide-coordinates", serde(with = "wide"))]
+    pub x: Coord,
+    #[cfg_attr(feature = "wide-coordinates", serde(with = "wide"))]
+    pub y: Coord,
+}
+
+/// TOML can't hold 128 bit integers, so wide coordinates are saved as strings.
+/// Numbers saved with 64 bit coordinates are still read.
+#[cfg(feature = "wide-coordinates")]
+mod wide {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use super::Coord;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Saved {
+        Number(i64),
+        Text(String),
+    }
+
+    pub fn serialize<S: Serializer>(coord: &Coord, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(coord)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Coord, D::Error> {
+        match Saved::deserialize(deserializer)? {
+            Saved::Number(coord) => Ok(coord.into()),
+            Saved::Text(coord) => coord.parse().map_err(de::Error::custom),
+        }
+    }
 }
 
 impl PartialEq for Node {
@@ -13,14 +53,25 @@ impl PartialEq for Node {
 impl Node {
     pub fn get_neighbors(&self) -> Vec<Node> {
         let mut neighbors = Vec::new();
-        for x in self.x..=(self.x + 2) {
-            for y in self.y..=(self.y + 2) {
-                neighbors.push(Node { x: x - 1, y: y - 1 })
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                if let (Some(x), Some(y)) = (self.x.checked_add(dx), self.y.checked_add(dy)) {
+                    neighbors.push(Node { x, y })
+                }
             }
         }
-        neighbors.remove(4);
         neighbors
     }
+
+    pub fn get_wrapped_neighbors(&self, topology: &Topology) -> Vec<Node> {
+        self.get_neighbors()
+            .iter()
+            .filter_map(|neighbor| topology.wrap(neighbor))
+            .collect()
+    }
 }
 
 
@@ -78,4 +129,36 @@ mod test {
         assert!(neighbors.contains(&Node {x: -1, y: -2}));
         assert!(neighbors.contains(&Node {x: -1, y: -1}));
     }
+
+    #[test]
+    fn get_wrapped_neighbors_on_torus_edge_wrap_around() {
+        let topology = Topology::Torus { width: 4, height: 4 };
+        let node = Node { x: 1, y: 1 };
+        let neighbors = node.get_wrapped_neighbors(&topology);
+        assert_eq!(neighbors.len(), 8);
+        assert!(neighbors.contains(&Node { x: -2, y: -2 }));
+        assert!(neighbors.contains(&Node { x: -2, y: 1 }));
+        assert!(neighbors.contains(&Node { x: 1, y: -2 }));
+        assert!(neighbors.contains(&Node { x: 0, y: 0 }));
+    }
+
+    #[test]
+    fn get_neighbors_beyond_i32_range() {
+        let node = Node { x: 3_000_000_000, y: -3_000_000_000 };
+        let neighbors = node.get_neighbors();
+        assert_eq!(neighbors.len(), 8);
+        assert!(neighbors.contains(&Node { x: 3_000_000_001, y: -3_000_000_001 }));
+    }
+
+    #[test]
+    fn get_neighbors_at_the_edge_of_the_universe_stops_at_the_edge() {
+        let node = Node { x: Coord::MAX, y: 0 };
+        assert_eq!(node.get_neighbors().len(), 5);
+        assert!(!node.get_neighbors().iter().any(|neighbor| neighbor.x < Coord::MAX - 1));
+
+        let node = Node { x: Coord::MIN, y: Coord::MAX };
+        let neighbors = node.get_neighbors();
+        assert_eq!(neighbors.len(), 3);
+        assert!(neighbors.contains(&Node { x: Coord::MIN + 1, y: Coord::MAX - 1 }));
+    }
 }
@@ -0,0 +1,181 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::game::Game;
+use crate::node::{Coord, Node};
+use crate::period::Periodicity;
+use crate::rule::Rule;
+
+/// A separate object of a game, its cells moved so the lower corner of their
+/// bounds is at the origin, and where that corner was.
+#[derive(Clone)]
+pub struct Object {
+    pub game: Game,
+    pub offset: Node,
+}
+
+impl Object {
+    fn new(rule: &Rule, mut cells: Vec<(Coord, Coord)>) -> Self {
+        cells.sort_unstable();
+        let left = cells.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let bottom = cells.iter().map(|&(_, y)| y).min().unwrap_or(0);
+        let mut game = Game::with_rule(rule.clone());
+        game.live_nodes = cells.into_iter().map(|(x, y)| Node { x: x - left, y: y - bottom }).collect();
+        Object { game, offset: Node { x: left, y: bottom } }
+    }
+
+    /// The object's cells where they were in the game.
+    pub fn get_cells(&self) -> Vec<(Coord, Coord)> {
+        self.game.live_nodes.iter().map(|node| (node.x + self.offset.x, node.y + self.offset.y)).collect()
+    }
+}
+
+/// Groups cells that are linked by chains of cells at most `distance` apart
+/// in both x and y, in the order of their first cell. Cells are kept in
+/// buckets `distance` wide, so linked cells are in the same or neighbouring
+/// buckets and the search doesn't grow with the distance.
+fn get_clusters(cells: &[(Coord, Coord)], distance: Coord) -> Vec<Vec<(Coord, Coord)>> {
+    let get_bucket = |(x, y): (Coord, Coord)| (x.div_euclid(distance), y.div_euclid(distance));
+    let mut remaining: HashSet<(Coord, Coord)> = cells.iter().copied().collect();
+    let mut ordered: Vec<(Coord, Coord)> = remaining.iter().copied().collect();
+    ordered.sort_unstable();
+    let mut buckets: HashMap<(Coord, Coord), Vec<(Coord, Coord)>> = HashMap::new();
+    for &cell in &ordered {
+        buckets.entry(get_bucket(cell)).or_default().push(cell);
+    }
+    let mut clusters = Vec::new();
+    for cell in ordered {
+        if !remaining.contains(&cell) {
+            continue;
+        }
+        let mut cluster = Vec::new();
+        let mut queue = VecDeque::from([cell]);
+        remaining.remove(&cell);
+        buckets.entry(get_bucket(cell)).or_default().retain(|&other| other != cell);
+        while let Some((x, y)) = queue.pop_front() {
+            cluster.push((x, y));
+            let (column, row) = get_bucket((x, y));
+            for bucket_x in [column.checked_sub(1), Some(column), column.checked_add(1)].into_iter().flatten() {
+                for bucket_y in [row.checked_sub(1), Some(row), row.checked_add(1)].into_iter().flatten() {
+                    let Some(bucket) = buckets.get_mut(&(bucket_x, bucket_y)) else { continue };
+                    bucket.retain(|&(other_x, other_y)| {
+                        let near = x.abs_diff(other_x) <= distance.unsigned_abs() && y.abs_diff(other_y) <= distance.unsigned_abs();
+                        if near {
+                            remaining.remove(&(other_x, other_y));
+                            queue.push_back((other_x, other_y));
+                        }
+                        !near
+                    });
+                }
+            }
+        }
+        clusters.push(cluster);
+    }
+    clusters
+}
+
+fn is_still_life(rule: &Rule, cells: &[(Coord, Coord)]) -> bool {
+    Object::new(rule, cells.to_vec()).game.detect_period(1) == Periodicity::StillLife
+}
+
+fn are_near(a: &[(Coord, Coord)], b: &[(Coord, Coord)]) -> bool {
+    a.iter().any(|&(ax, ay)| b.iter().any(|&(bx, by)| (ax - bx).abs() <= 2 && (ay - by).abs() <= 2))
+}
+
+/// Splits a still life into the touching pieces that are still lifes on their
+/// own, the way apgsearch counts a pseudo still life such as the bi-block as
+/// two blocks. Pieces that aren't stable alone are merged with the pieces
+/// they lean on until every group is.
+fn split_pseudo_still_life(rule: &Rule, cluster: Vec<(Coord, Coord)>) -> Vec<Vec<(Coord, Coord)>> {
+    let mut groups = get_clusters(&cluster, 1);
+    if groups.len() == 1 {
+        return groups;
+    }
+    while let Some(index) = groups.iter().position(|group| !is_still_life(rule, group)) {
+        let mut group = groups.swap_remove(index);
+        let (near, far): (Vec<_>, Vec<_>) = groups.into_iter().partition(|other| are_near(&group, other));
+        if near.is_empty() {
+            return vec![cluster];
+        }
+        for other in near {
+            group.extend(other);
+        }
+        groups = far;
+        groups.push(group);
+    }
+    groups.sort_unstable_by_key(|group| group.iter().min().copied());
+    groups
+}
+
+impl Game {
+    /// Splits the live cells into objects of cells linked by chains of cells
+    /// at most `distance` apart in both x and y. A distance of 2, one dead
+    /// cell between them, keeps together anything that can interact in the
+    /// next generation. Still lifes are split further into pieces that are
+    /// stable on their own, as apgsearch does with pseudo still lifes.
+    pub fn split_objects(&self, distance: Coord) -> Vec<Object> {
+        let cells: Vec<(Coord, Coord)> = self.live_nodes.iter().map(|node| (node.x, node.y)).collect();
+        get_clusters(&cells, distance.max(1))
+            .into_iter()
+            .flat_map(|cluster| {
+                if is_still_life(&self.rule, &cluster) {
+                    split_pseudo_still_life(&self.rule, cluster)
+                } else {
+                    vec![cluster]
+                }
+            })
+            .map(|cells| Object::new(&self.rule, cells))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn split(rle: &str, distance: Coord) -> Vec<(String, Coord, Coord)> {
+        Game::from_rle(rle).unwrap()
+            .split_objects(distance)
+            .iter()
+            .map(|object| (object.game.apgcode(30).unwrap_or_default(), object.offset.x, object.offset.y))
+            .collect()
+    }
+
+    #[test]
+    fn splits_distant_objects_with_their_offsets() {
+        assert_eq!(split("2o5b3o$2o!", 2), vec![("xs4_33".to_string(), 0, 0), ("xp2_7".to_string(), 7, 1)]);
+        let objects = Game::from_rle("2o5b3o$2o!").unwrap().split_objects(2);
+        let mut cells = objects[1].get_cells();
+        cells.sort();
+        assert_eq!(cells, vec![(7, 1), (8, 1), (9, 1)]);
+    }
+
+    #[test]
+    fn keeps_objects_within_the_distance_together() {
+        assert_eq!(split("3o2b3o!", 2).len(), 2);
+        assert_eq!(split("3o2b3o!", 3).len(), 1);
+        assert_eq!(split("3ob3o!", 2).len(), 1);
+    }
+
+    #[test]
+    fn groups_cells_at_any_distance_without_searching_every_offset() {
+        let cells = [(0, 0), (1_000_000, 0), (3_000_000, 5), (Coord::MIN, Coord::MAX)];
+        assert_eq!(get_clusters(&cells, 1_000_000), vec![vec![(Coord::MIN, Coord::MAX)], vec![(0, 0), (1_000_000, 0)], vec![(3_000_000, 5)]]);
+        assert_eq!(get_clusters(&cells, 2_000_000).len(), 2);
+        assert_eq!(get_clusters(&cells, Coord::MAX).len(), 2);
+        assert_eq!(get_clusters(&cells[..3], Coord::MAX).len(), 1);
+        assert_eq!(get_clusters(&[(Coord::MAX, 0), (Coord::MAX - 1, 1), (Coord::MIN, 0)], 1).len(), 2);
+    }
+
+    #[test]
+    fn splits_pseudo_still_lifes() {
+        assert_eq!(split("2ob2o$2ob2o!", 2), vec![("xs4_33".to_string(), 0, 0), ("xs4_33".to_string(), 3, 0)]);
+        assert_eq!(split("b2o$o2bo$b2o!", 2), vec![("xs6_696".to_string(), 0, 0)]);
+    }
+
+    #[test]
+    fn keeps_still_lifes_whose_pieces_are_not_stable_alone() {
+        // Table on table: neither table is stable on its own.
+        let game = Game::from_rle("o2bo$4o2$4o$o2bo!").unwrap();
+        assert_eq!(game.detect_period(1), Periodicity::StillLife);
+        assert_eq!(game.split_objects(2).len(), 1);
+    }
+}
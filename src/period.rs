@@ -0,0 +1,232 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use crate::game::Game;
+use crate::node::{Coord, Node};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Periodicity {
+    StillLife,
+    /// Comes back to the same cells after this many generations, more than 1.
+    Oscillator(u64),
+    /// Dies out, changes for good, or doesn't come back within the budget.
+    Aperiodic,
+}
+
+fn hash_sorted_cells(cells: &[(Coord, Coord)]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    cells.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A hash of the live cells that doesn't depend on their order.
+pub fn hash_cells(game: &Game) -> u64 {
+    hash_sorted_cells(&game.get_sorted_cells())
+}
+
+/// A pattern that comes back to the same shape, moved by dx, dy, after
+/// `period` generations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spaceship {
+    pub period: u64,
+    pub dx: Coord,
+    pub dy: Coord,
+}
+
+fn gcd(a: Coord, b: Coord) -> Coord {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+impl Spaceship {
+    /// The speed in c notation with the direction, e.g. `c/4 diagonal` for
+    /// the glider, `2c/5 orthogonal` or `(2,1)c/6 oblique` for knightships.
+    pub fn get_speed(&self) -> String {
+        let (dx, dy) = (self.dx.abs(), self.dy.abs());
+        let (long, short) = (dx.max(dy), dx.min(dy));
+        let period = self.period as Coord;
+        if short != 0 && short != long {
+            return format!("({},{})c/{} oblique", long, short, period);
+        }
+        let divisor = gcd(long, period);
+        let (distance, period) = (long / divisor, period / divisor);
+        let direction = if short == 0 { "orthogonal" } else { "diagonal" };
+        match distance {
+            1 => format!("c/{} {}", period, direction),
+            distance => format!("{}c/{} {}", distance, period, direction),
+        }
+    }
+}
+
+impl fmt::Display for Spaceship {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} moving ({}, {}) every {} generations", self.get_speed(), self.dx, self.dy, self.period)
+    }
+}
+
+/// The sorted live cells moved so the lower corner of their bounds is at the
+/// origin, and that corner, or `None` if there are none or they span more than
+/// `Coord::MAX` in x or y.
+fn get_normalized_cells(game: &Game) -> Option<(Vec<(Coord, Coord)>, Node)> {
+    let lower = game.get_live_boundary()?.lower;
+    let cells = game.get_sorted_cells()
+        .into_iter()
+        .map(|(x, y)| Some((x.checked_sub(lower.x)?, y.checked_sub(lower.y)?)))
+        .collect::<Option<_>>()?;
+    Some((cells, lower))
+}
+
+/// Evolves a copy of the game until it comes back to the same shape, passing
+/// every generation before that to `visit`, starting with the game itself.
+fn search_translation(game: &Game, max_gens: u64, mut visit: impl FnMut(&Game)) -> Option<(u64, Coord, Coord)> {
+    let (start_cells, corner) = get_normalized_cells(game)?;
+    let start = hash_sorted_cells(&start_cells);
+    let mut seen = HashSet::new();
+    let mut current = game.clone();
+    for generation in 1..=max_gens {
+        visit(&current);
+        current.evolve();
+        let (cells, lower) = get_normalized_cells(&current)?;
+        let hash = hash_sorted_cells(&cells);
+        if hash == start && cells == start_cells {
+            return Some((generation, lower.x - corner.x, lower.y - corner.y));
+        }
+        if !seen.insert(hash) {
+            return None;
+        }
+    }
+    None
+}
+
+/// How many generations the game takes to come back to the same shape,
+/// anywhere, and how far it moves in that time, if it does within `max_gens`
+/// generations without dying out or spreading over more than `Coord::MAX`
+/// cells.
+pub fn find_translation_period(game: &Game, max_gens: u64) -> Option<(u64, Coord, Coord)> {
+    search_translation(game, max_gens, |_| {})
+}
+
+/// Every phase of the game, starting with the game itself, and how far it
+/// moves in one period, as `find_translation_period` finds them.
+pub fn find_translation_phases(game: &Game, max_gens: u64) -> Option<(Vec<Game>, Coord, Coord)> {
+    let mut phases = Vec::new();
+    let (_, dx, dy) = search_translation(game, max_gens, |phase| phases.push(phase.clone()))?;
+    Some((phases, dx, dy))
+}
+
+impl Game {
+    /// Evolves a copy of the game for up to `max_gens` generations to find
+    /// whether it comes back to its own cells. Generations are compared by
+    /// `hash_cells`, with the cells checked when the hash matches the first
+    /// generation's, and a repeat of any later generation stops the search
+    /// early since the first one can't come back after it. An empty game is
+    /// aperiodic.
+    pub fn detect_period(&self, max_gens: u64) -> Periodicity {
+        if self.live_nodes.is_empty() {
+            return Periodicity::Aperiodic;
+        }
+        let start_cells = self.get_sorted_cells();
+        let start = hash_sorted_cells(&start_cells);
+        let mut seen = HashSet::new();
+        let mut current = self.clone();
+        for generation in 1..=max_gens {
+            current.evolve();
+            if current.live_nodes.is_empty() {
+                return Periodicity::Aperiodic;
+            }
+            let hash = hash_cells(&current);
+            if hash == start && current.get_sorted_cells() == start_cells {
+                return match generation {
+                    1 => Periodicity::StillLife,
+                    period => Periodicity::Oscillator(period),
+                };
+            }
+            if !seen.insert(hash) {
+                return Periodicity::Aperiodic;
+            }
+        }
+        Periodicity::Aperiodic
+    }
+
+    /// Finds whether the game comes back to its own shape somewhere else
+    /// within `max_gens` generations. Still lifes and oscillators aren't
+    /// spaceships.
+    pub fn detect_spaceship(&self, max_gens: u64) -> Option<Spaceship> {
+        match find_translation_period(self, max_gens)? {
+            (_, 0, 0) => None,
+            (period, dx, dy) => Some(Spaceship { period, dx, dy }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn detects_still_lifes() {
+        assert_eq!(Game::from_rle("2o$2o!").unwrap().detect_period(10), Periodicity::StillLife);
+        assert_eq!(Game::from_rle("b2o$o2bo$b2o!").unwrap().detect_period(10), Periodicity::StillLife);
+    }
+
+    #[test]
+    fn detects_oscillators() {
+        assert_eq!(Game::from_rle("3o!").unwrap().detect_period(10), Periodicity::Oscillator(2));
+        let pentadecathlon = Game::from_rle("2bo4bo$2ob4ob2o$2bo4bo!").unwrap();
+        assert_eq!(pentadecathlon.detect_period(20), Periodicity::Oscillator(15));
+        assert_eq!(pentadecathlon.detect_period(14), Periodicity::Aperiodic);
+    }
+
+    #[test]
+    fn detects_patterns_that_do_not_come_back() {
+        let glider = Game::from_rle("bo$2bo$3o!").unwrap();
+        assert_eq!(glider.detect_period(100), Periodicity::Aperiodic);
+        assert_eq!(Game::from_rle("2o!").unwrap().detect_period(10), Periodicity::Aperiodic);
+        assert_eq!(Game::from_rle("o$2o!").unwrap().detect_period(10), Periodicity::Aperiodic);
+        assert_eq!(Game::new().detect_period(10), Periodicity::Aperiodic);
+    }
+
+    #[test]
+    fn hashes_cells_in_any_order() {
+        let mut game = Game::from_rle("bo$2bo$3o!").unwrap();
+        let hash = hash_cells(&game);
+        game.live_nodes.reverse();
+        assert_eq!(hash_cells(&game), hash);
+        game.live_nodes.push(Node { x: 10, y: 10 });
+        assert_ne!(hash_cells(&game), hash);
+    }
+
+    #[test]
+    fn detects_spaceships() {
+        let glider = Game::from_rle("bo$2bo$3o!").unwrap().detect_spaceship(10).unwrap();
+        assert_eq!(glider, Spaceship { period: 4, dx: 1, dy: -1 });
+        assert_eq!(glider.get_speed(), "c/4 diagonal");
+        let lightweight = Game::from_rle("o2bo$4bo$o3bo$b4o!").unwrap().detect_spaceship(10).unwrap();
+        assert_eq!(lightweight, Spaceship { period: 4, dx: 2, dy: 0 });
+        assert_eq!(lightweight.get_speed(), "c/2 orthogonal");
+        assert_eq!(Game::from_rle("bo$2bo$3o!").unwrap().detect_spaceship(3), None);
+        assert_eq!(Game::from_rle("3o!").unwrap().detect_spaceship(10), None);
+        assert_eq!(Game::from_rle("2o!").unwrap().detect_spaceship(10), None);
+    }
+
+    #[test]
+    fn finds_every_phase_in_one_pass() {
+        let glider = Game::from_rle("bo$2bo$3o!").unwrap();
+        let (phases, dx, dy) = find_translation_phases(&glider, 10).unwrap();
+        assert_eq!((phases.len(), dx, dy), (4, 1, -1));
+        assert_eq!(phases[0].get_sorted_cells(), glider.get_sorted_cells());
+        let mut next = glider.clone();
+        next.evolve();
+        assert_eq!(phases[1].get_sorted_cells(), next.get_sorted_cells());
+        assert!(find_translation_phases(&glider, 3).is_none());
+    }
+
+    #[test]
+    fn writes_speeds_in_c_notation() {
+        assert_eq!(Spaceship { period: 5, dx: 0, dy: -2 }.get_speed(), "2c/5 orthogonal");
+        assert_eq!(Spaceship { period: 6, dx: -2, dy: 0 }.get_speed(), "c/3 orthogonal");
+        assert_eq!(Spaceship { period: 12, dx: 2, dy: 2 }.get_speed(), "c/6 diagonal");
+        assert_eq!(Spaceship { period: 6, dx: 1, dy: -2 }.get_speed(), "(2,1)c/6 oblique");
+        assert_eq!(Spaceship { period: 4, dx: 1, dy: 1 }.to_string(), "c/4 diagonal moving (1, 1) every 4 generations");
+    }
+}
@@ -0,0 +1,642 @@
+use std::collections::HashMap;
+use std::fmt;
+use crate::game::Game;
+use crate::node::{Coord, Node};
+use crate::rule::Rule;
+use crate::topology::Topology;
+
+/// Index of a quad in a `QuadTree`'s store. Equal quads share one index.
+pub type QuadId = usize;
+
+/// The largest level whose nodes all have coordinates that fit in a `Coord`.
+pub const MAX_LEVEL: u32 = Coord::BITS - 1;
+
+/// How far from the origin a node may be, on either axis, to fit in a
+/// universe no bigger than `MAX_LEVEL`.
+pub const MAX_DISTANCE: Coord = 1 << (MAX_LEVEL - 1);
+
+const DEAD: QuadId = 0;
+const ALIVE: QuadId = 1;
+
+/// A square of 2^level by 2^level nodes: a single node at level 0, four
+/// quadrants of the level below otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quad {
+    Leaf(bool),
+    Branch { level: u32, nw: QuadId, ne: QuadId, sw: QuadId, se: QuadId },
+}
+
+impl Quad {
+    pub fn level(&self) -> u32 {
+        match *self {
+            Quad::Leaf(_) => 0,
+            Quad::Branch { level, .. } => level,
+        }
+    }
+}
+
+/// A node placed, or a pattern grown, too far from the origin for the
+/// universe to hold.
+#[derive(Debug, PartialEq)]
+pub struct RangeError(String);
+
+impl RangeError {
+    pub fn new(message: impl Into<String>) -> Self {
+        RangeError(message.into())
+    }
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "range error: {}", self.0)
+    }
+}
+
+impl std::error::Error for RangeError {}
+
+/// A hashlife universe. Identical quads are stored once, so huge regular
+/// patterns take little memory, and the result of evolving a quad is
+/// remembered, so repeated structure is only computed once.
+///
+/// Coordinates inside the tree point y down, like the pattern formats; the
+/// conversions to and from `Game` flip y. Hashlife only works on the plane, so
+/// a tree with a bounded topology steps through a `Game` instead.
+pub struct QuadTree {
+    rule: Rule,
+    topology: Topology,
+    generation: u64,
+    quads: Vec<Quad>,
+    populations: Vec<u64>,
+    index: HashMap<Quad, QuadId>,
+    empty: Vec<QuadId>,
+    results: HashMap<(QuadId, u32), QuadId>,
+    root: QuadId,
+}
+
+impl QuadTree {
+    pub fn new() -> Self {
+        let mut tree = QuadTree {
+            rule: Rule::conway(),
+            topology: Topology::Plane,
+            generation: 0,
+            quads: vec![Quad::Leaf(false), Quad::Leaf(true)],
+            populations: vec![0, 1],
+            index: HashMap::new(),
+            empty: vec![DEAD],
+            results: HashMap::new(),
+            root: DEAD,
+        };
+        tree.index.insert(Quad::Leaf(false), DEAD);
+        tree.index.insert(Quad::Leaf(true), ALIVE);
+        tree.root = tree.get_empty(3);
+        tree
+    }
+
+    pub fn get_rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// Changes the rule, forgetting every result computed with the old one.
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+        self.results.clear();
+    }
+
+    pub fn get_topology(&self) -> &Topology {
+        &self.topology
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn get_leaf(&self, alive: bool) -> QuadId {
+        if alive { ALIVE } else { DEAD }
+    }
+
+    pub fn get_quad(&self, id: QuadId) -> Quad {
+        self.quads[id]
+    }
+
+    pub fn get_population_of(&self, id: QuadId) -> u64 {
+        self.populations[id]
+    }
+
+    pub fn get_root(&self) -> QuadId {
+        self.root
+    }
+
+    pub fn get_level(&self) -> u32 {
+        self.quads[self.root].level()
+    }
+
+    pub fn get_population(&self) -> u64 {
+        self.populations[self.root]
+    }
+
+    pub fn get_generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn set_generation(&mut self, generation: u64) {
+        self.generation = generation;
+    }
+
+    /// Number of distinct quads in the store, which stays small for patterns
+    /// built out of repeated pieces.
+    pub fn get_quad_count(&self) -> usize {
+        self.quads.len()
+    }
+
+    /// Interns a branch, returning the index of the equal quad if there is one.
+    pub fn join(&mut self, nw: QuadId, ne: QuadId, sw: QuadId, se: QuadId) -> QuadId {
+        let level = self.quads[nw].level() + 1;
+        let quad = Quad::Branch { level, nw, ne, sw, se };
+        if let Some(&id) = self.index.get(&quad) {
+            return id;
+        }
+        let population = [nw, ne, sw, se].iter()
+            .fold(0u64, |total, &child| total.saturating_add(self.populations[child]));
+        let id = self.quads.len();
+        self.quads.push(quad);
+        self.populations.push(population);
+        self.index.insert(quad, id);
+        id
+    }
+
+    pub fn get_empty(&mut self, level: u32) -> QuadId {
+        while self.empty.len() <= level as usize {
+            let below = *self.empty.last().unwrap();
+            let empty = self.join(below, below, below, below);
+            self.empty.push(empty);
+        }
+        self.empty[level as usize]
+    }
+
+    /// Replaces the whole universe with the quad `root`, centered on the origin.
+    pub fn set_root(&mut self, root: QuadId) {
+        self.root = root;
+        while self.get_level() < 3 {
+            self.expand();
+        }
+    }
+
+    fn children(&self, id: QuadId) -> (QuadId, QuadId, QuadId, QuadId) {
+        match self.quads[id] {
+            Quad::Branch { nw, ne, sw, se, .. } => (nw, ne, sw, se),
+            Quad::Leaf(_) => panic!("a leaf has no children"),
+        }
+    }
+
+    fn half(&self) -> Coord {
+        1 << (self.get_level() - 1)
+    }
+
+    /// Doubles the size of the universe, keeping the pattern centered.
+    fn expand(&mut self) {
+        let level = self.quads[self.root].level();
+        let (nw, ne, sw, se) = if level == 0 {
+            (DEAD, DEAD, DEAD, self.root)
+        } else {
+            self.children(self.root)
+        };
+        let empty = self.get_empty(level.saturating_sub(1));
+        let nw = self.join(empty, empty, empty, nw);
+        let ne = self.join(empty, empty, ne, empty);
+        let sw = self.join(empty, sw, empty, empty);
+        let se = self.join(se, empty, empty, empty);
+        self.root = self.join(nw, ne, sw, se);
+    }
+
+    fn contains(&self, x: Coord, y: Coord) -> bool {
+        let half = self.half();
+        x >= -half && x < half && y >= -half && y < half
+    }
+
+    /// Whether the node at x, y, with y pointing down, is alive.
+    pub fn is_alive(&self, x: Coord, y: Coord) -> bool {
+        if !self.contains(x, y) {
+            return false;
+        }
+        let half = self.half();
+        let (mut id, mut x, mut y) = (self.root, x + half, y + half);
+        let mut size = half;
+        while let Quad::Branch { nw, ne, sw, se, .. } = self.quads[id] {
+            id = match (x >= size, y >= size) {
+                (false, false) => nw,
+                (true, false) => ne,
+                (false, true) => sw,
+                (true, true) => se,
+            };
+            x %= size;
+            y %= size;
+            size /= 2;
+        }
+        id == ALIVE
+    }
+
+    /// Sets the node at x, y, with y pointing down. Fails to make a node alive
+    /// `MAX_DISTANCE` or further from the origin.
+    pub fn set_alive(&mut self, x: Coord, y: Coord, alive: bool) -> Result<(), RangeError> {
+        let fits = |value: Coord| (-MAX_DISTANCE..MAX_DISTANCE).contains(&value);
+        if !fits(x) || !fits(y) {
+            return match alive {
+                true => Err(RangeError::new(format!("node {}, {} is too far from the origin", x, y))),
+                false => Ok(()),
+            };
+        }
+        while !self.contains(x, y) {
+            self.expand();
+        }
+        let half = self.half();
+        self.root = self.set_in(self.root, x + half, y + half, alive);
+        Ok(())
+    }
+
+    fn set_in(&mut self, id: QuadId, x: Coord, y: Coord, alive: bool) -> QuadId {
+        let level = self.quads[id].level();
+        if level == 0 {
+            return if alive { ALIVE } else { DEAD };
+        }
+        let size = 1 << (level - 1);
+        let (mut nw, mut ne, mut sw, mut se) = self.children(id);
+        let (cx, cy) = (x % size, y % size);
+        match (x >= size, y >= size) {
+            (false, false) => nw = self.set_in(nw, cx, cy, alive),
+            (true, false) => ne = self.set_in(ne, cx, cy, alive),
+            (false, true) => sw = self.set_in(sw, cx, cy, alive),
+            (true, true) => se = self.set_in(se, cx, cy, alive),
+        }
+        self.join(nw, ne, sw, se)
+    }
+
+    /// Every live node with y pointing down.
+    pub fn get_live_cells(&self) -> Vec<(Coord, Coord)> {
+        let mut cells = Vec::new();
+        let half = self.half();
+        self.collect_cells(self.root, -half, -half, &mut cells);
+        cells
+    }
+
+    fn collect_cells(&self, id: QuadId, x: Coord, y: Coord, cells: &mut Vec<(Coord, Coord)>) {
+        if self.populations[id] == 0 {
+            return;
+        }
+        match self.quads[id] {
+            Quad::Leaf(_) => cells.push((x, y)),
+            Quad::Branch { level, nw, ne, sw, se } => {
+                let size = 1 << (level - 1);
+                self.collect_cells(nw, x, y, cells);
+                self.collect_cells(ne, x + size, y, cells);
+                self.collect_cells(sw, x, y + size, cells);
+                self.collect_cells(se, x + size, y + size, cells);
+            }
+        }
+    }
+
+    /// The game's nodes, rule and topology, failing on a node `MAX_DISTANCE`
+    /// or further from the origin.
+    pub fn from_game(game: &Game) -> Result<Self, RangeError> {
+        let mut tree = QuadTree::new();
+        tree.set_rule(game.rule.clone());
+        tree.set_topology(game.topology.clone());
+        for node in &game.live_nodes {
+            let y = node.y.checked_neg()
+                .ok_or_else(|| RangeError::new(format!("node {}, {} is too far from the origin", node.x, node.y)))?;
+            tree.set_alive(node.x, y, true)?;
+        }
+        Ok(tree)
+    }
+
+    pub fn to_game(&self) -> Game {
+        let mut game = Game { rule: self.rule.clone(), topology: self.topology.clone(), ..Game::new() };
+        game.live_nodes = self.get_live_cells()
+            .into_iter()
+            .map(|(x, y)| Node { x, y: -y })
+            .collect();
+        game
+    }
+
+    /// Advances the universe by `generations`, taking the largest power of
+    /// two steps hashlife allows. Fails, at the generation it reached, once
+    /// the pattern would grow past `MAX_LEVEL`.
+    pub fn step(&mut self, generations: u64) -> Result<(), RangeError> {
+        if self.topology != Topology::Plane {
+            return self.step_bounded(generations);
+        }
+        let mut remaining = generations;
+        let mut step_log2 = 0;
+        while remaining > 0 {
+            if remaining & 1 == 1 {
+                self.step_pow2(step_log2)?;
+            }
+            remaining >>= 1;
+            step_log2 += 1;
+        }
+        Ok(())
+    }
+
+    /// Advances the universe by 2^step_log2 generations.
+    pub fn step_pow2(&mut self, step_log2: u32) -> Result<(), RangeError> {
+        if self.topology != Topology::Plane {
+            return self.step_bounded(1u64.checked_shl(step_log2).unwrap_or(u64::MAX));
+        }
+        // A quad only steps 2^(level - 2) generations at once, so longer
+        // steps are split in halves rather than growing past `MAX_LEVEL`.
+        if step_log2 > MAX_LEVEL - 2 {
+            self.step_pow2(step_log2 - 1)?;
+            return self.step_pow2(step_log2 - 1);
+        }
+        while self.get_level() < step_log2 + 2 || !self.is_padded() {
+            if self.get_level() >= MAX_LEVEL {
+                return Err(RangeError::new("the pattern grew too far from the origin"));
+            }
+            self.expand();
+        }
+        // The root is one level past `MAX_LEVEL` at most here, and the
+        // successor is one level down again.
+        self.expand();
+        self.root = self.successor(self.root, step_log2);
+        self.generation = self.generation.saturating_add(1u64.checked_shl(step_log2).unwrap_or(u64::MAX));
+        Ok(())
+    }
+
+    /// Evolves a bounded topology one generation at a time, which hashlife
+    /// can't do since its quads don't wrap.
+    fn step_bounded(&mut self, generations: u64) -> Result<(), RangeError> {
+        let mut game = self.to_game();
+        for _ in 0..generations {
+            game.evolve();
+        }
+        let generation = self.generation.saturating_add(generations);
+        *self = QuadTree::from_game(&game)?;
+        self.generation = generation;
+        Ok(())
+    }
+
+    /// Whether the live nodes all sit in the middle half of the universe.
+    fn is_padded(&self) -> bool {
+        let (nw, ne, sw, se) = self.children(self.root);
+        let inner = |tree: &Self, id: QuadId, inner: fn(&Self, QuadId) -> QuadId| {
+            tree.populations[id] == tree.populations[inner(tree, id)]
+        };
+        inner(self, nw, |tree, id| tree.children(id).3)
+            && inner(self, ne, |tree, id| tree.children(id).2)
+            && inner(self, sw, |tree, id| tree.children(id).1)
+            && inner(self, se, |tree, id| tree.children(id).0)
+    }
+
+    /// The middle quad, one level down, of `id` after 2^step_log2 generations.
+    /// Needs `step_log2 <= level - 2`.
+    fn successor(&mut self, id: QuadId, step_log2: u32) -> QuadId {
+        let level = self.quads[id].level();
+        if self.populations[id] == 0 {
+            return self.get_empty(level - 1);
+        }
+        if level == 2 {
+            return self.evolve_level_2(id);
+        }
+        if let Some(&result) = self.results.get(&(id, step_log2)) {
+            return result;
+        }
+        let (nw, ne, sw, se) = self.children(id);
+        let (g00, g01, g10, g11) = self.children(nw);
+        let (g02, g03, g12, g13) = self.children(ne);
+        let (g20, g21, g30, g31) = self.children(sw);
+        let (g22, g23, g32, g33) = self.children(se);
+        let grid = [
+            [g00, g01, g02, g03],
+            [g10, g11, g12, g13],
+            [g20, g21, g22, g23],
+            [g30, g31, g32, g33],
+        ];
+        // A full step of 2^(level - 2) generations is taken in two halves,
+        // a shorter one in a single pass followed by cropping.
+        let full_step = step_log2 == level - 2;
+        let inner_step = if full_step { step_log2 - 1 } else { step_log2 };
+        let mut nine = [[DEAD; 3]; 3];
+        for i in 0..3 {
+            for j in 0..3 {
+                let overlap = self.join(grid[i][j], grid[i][j + 1], grid[i + 1][j], grid[i + 1][j + 1]);
+                nine[i][j] = self.successor(overlap, inner_step);
+            }
+        }
+        let result = if full_step {
+            let mut quadrants = [DEAD; 4];
+            for (index, (i, j)) in [(0, 0), (0, 1), (1, 0), (1, 1)].into_iter().enumerate() {
+                let quadrant = self.join(nine[i][j], nine[i][j + 1], nine[i + 1][j], nine[i + 1][j + 1]);
+                quadrants[index] = self.successor(quadrant, inner_step);
+            }
+            self.join(quadrants[0], quadrants[1], quadrants[2], quadrants[3])
+        } else {
+            let mut quadrants = [DEAD; 4];
+            for (index, (i, j)) in [(0, 0), (0, 1), (1, 0), (1, 1)].into_iter().enumerate() {
+                let nw = self.children(nine[i][j]).3;
+                let ne = self.children(nine[i][j + 1]).2;
+                let sw = self.children(nine[i + 1][j]).1;
+                let se = self.children(nine[i + 1][j + 1]).0;
+                quadrants[index] = self.join(nw, ne, sw, se);
+            }
+            self.join(quadrants[0], quadrants[1], quadrants[2], quadrants[3])
+        };
+        self.results.insert((id, step_log2), result);
+        result
+    }
+
+    /// The middle 2x2 of a 4x4 quad after one generation.
+    fn evolve_level_2(&mut self, id: QuadId) -> QuadId {
+        let mut cells = [[false; 4]; 4];
+        let (nw, ne, sw, se) = self.children(id);
+        for (quadrant, (x, y)) in [(nw, (0, 0)), (ne, (2, 0)), (sw, (0, 2)), (se, (2, 2))] {
+            let (a, b, c, d) = self.children(quadrant);
+            cells[y][x] = a == ALIVE;
+            cells[y][x + 1] = b == ALIVE;
+            cells[y + 1][x] = c == ALIVE;
+            cells[y + 1][x + 1] = d == ALIVE;
+        }
+        let mut next = [DEAD; 4];
+        for (index, (x, y)) in [(1, 1), (2, 1), (1, 2), (2, 2)].into_iter().enumerate() {
+            let live_neighbors = cells[y - 1..=y + 1].iter()
+                .flat_map(|row| &row[x - 1..=x + 1])
+                .filter(|&&alive| alive)
+                .count() - cells[y][x] as usize;
+            let alive = if cells[y][x] {
+                self.rule.survives(live_neighbors)
+            } else {
+                self.rule.is_born(live_neighbors)
+            };
+            next[index] = if alive { ALIVE } else { DEAD };
+        }
+        self.join(next[0], next[1], next[2], next[3])
+    }
+}
+
+impl Game {
+    /// Evolves the game `generations` times, with hashlife on the unbounded
+    /// plane and one generation at a time on bounded grids. Fails, leaving the
+    /// game as it was, if the pattern grows too far from the origin.
+    pub fn step(&mut self, generations: u64) -> Result<(), RangeError> {
+        if self.topology == Topology::Plane && generations > 1 {
+            let mut tree = QuadTree::from_game(self)?;
+            tree.step(generations)?;
+            *self = tree.to_game();
+        } else {
+            for _ in 0..generations {
+                self.evolve();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for QuadTree {
+    fn default() -> Self {
+        QuadTree::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn equal_quads_are_stored_once() {
+        let mut tree = QuadTree::new();
+        let block = tree.join(ALIVE, ALIVE, ALIVE, ALIVE);
+        assert_eq!(tree.join(ALIVE, ALIVE, ALIVE, ALIVE), block);
+        let count = tree.get_quad_count();
+        let big = tree.join(block, block, block, block);
+        tree.join(block, block, block, block);
+        assert_eq!(tree.get_quad_count(), count + 1);
+        assert_eq!(tree.get_population_of(big), 16);
+    }
+
+    #[test]
+    fn sets_and_reads_nodes_far_from_origin() {
+        let mut tree = QuadTree::new();
+        tree.set_alive(1_000_000, -3, true).unwrap();
+        tree.set_alive(-5, 7, true).unwrap();
+        assert!(tree.is_alive(1_000_000, -3));
+        assert!(tree.is_alive(-5, 7));
+        assert!(!tree.is_alive(0, 0));
+        assert_eq!(tree.get_population(), 2);
+        tree.set_alive(-5, 7, false).unwrap();
+        assert_eq!(tree.get_population(), 1);
+    }
+
+    #[test]
+    fn converts_to_and_from_game() {
+        let game = Game::from_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+        let tree = QuadTree::from_game(&game).unwrap();
+        assert_eq!(tree.get_population(), 5);
+        assert_eq!(tree.to_game().get_sorted_cells(), game.get_sorted_cells());
+    }
+
+    #[test]
+    fn steps_like_game_one_generation_at_a_time() {
+        let mut game = Game::from_rle("x = 3, y = 3\nb2o$2o$bo!").unwrap();
+        let mut tree = QuadTree::from_game(&game).unwrap();
+        for _ in 0..30 {
+            game.evolve();
+            tree.step(1).unwrap();
+            assert_eq!(tree.to_game().get_sorted_cells(), game.get_sorted_cells());
+        }
+        assert_eq!(tree.get_generation(), 30);
+    }
+
+    #[test]
+    fn steps_like_game_many_generations_at_once() {
+        let mut game = Game::from_rle("x = 3, y = 3\nb2o$2o$bo!").unwrap();
+        let mut tree = QuadTree::from_game(&game).unwrap();
+        for _ in 0..37 {
+            game.evolve();
+        }
+        tree.step(37).unwrap();
+        assert_eq!(tree.to_game().get_sorted_cells(), game.get_sorted_cells());
+    }
+
+    #[test]
+    fn glider_travels_far_in_few_steps() {
+        let game = Game::from_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+        let mut tree = QuadTree::from_game(&game).unwrap();
+        tree.step(1 << 20).unwrap();
+        let moved = tree.to_game();
+        let expected: Vec<(Coord, Coord)> = game.get_sorted_cells()
+            .into_iter()
+            .map(|(x, y)| (x + (1 << 18), y - (1 << 18)))
+            .collect();
+        assert_eq!(moved.get_sorted_cells(), expected);
+    }
+
+    #[test]
+    fn keeps_and_steps_bounded_topologies() {
+        let mut game = Game::from_rle("x = 3, y = 3, rule = B3/S23:T8,8\nbo$2bo$3o!").unwrap();
+        let mut tree = QuadTree::from_game(&game).unwrap();
+        assert_eq!(tree.to_game().topology, game.topology);
+        for _ in 0..20 {
+            game.evolve();
+        }
+        tree.step(20).unwrap();
+        assert_eq!(tree.to_game().get_sorted_cells(), game.get_sorted_cells());
+        assert_eq!(tree.get_generation(), 20);
+    }
+
+    #[test]
+    fn follows_its_rule() {
+        let mut game = Game::from_rle("x = 3, y = 3, rule = B36/S23\nobo$o$obo!").unwrap();
+        let mut tree = QuadTree::from_game(&game).unwrap();
+        game.evolve();
+        tree.step(1).unwrap();
+        assert_eq!(tree.to_game().get_sorted_cells(), game.get_sorted_cells());
+    }
+
+    #[test]
+    fn rejects_nodes_too_far_from_the_origin() {
+        let game = Game { live_nodes: vec![Node { x: Coord::MAX, y: 0 }], ..Game::new() };
+        assert!(QuadTree::from_game(&game).is_err());
+        let mut tree = QuadTree::new();
+        assert!(tree.set_alive(0, Coord::MIN, true).is_err());
+        assert!(tree.set_alive(0, Coord::MIN, false).is_ok());
+        tree.set_alive(MAX_DISTANCE - 1, -MAX_DISTANCE, true).unwrap();
+        assert!(tree.is_alive(MAX_DISTANCE - 1, -MAX_DISTANCE));
+        assert_eq!(tree.get_level(), MAX_LEVEL);
+        assert_eq!(tree.get_live_cells(), vec![(MAX_DISTANCE - 1, -MAX_DISTANCE)]);
+    }
+
+    #[test]
+    fn stops_stepping_patterns_that_grow_too_far() {
+        let mut glider = Game::from_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+        glider.live_nodes.iter_mut().for_each(|node| node.x += MAX_DISTANCE - 3);
+        let mut tree = QuadTree::from_game(&glider).unwrap();
+        assert!(tree.step(1).is_err());
+        assert!(tree.get_level() <= MAX_LEVEL);
+        assert_eq!(tree.to_game().get_sorted_cells(), glider.get_sorted_cells());
+
+        let block = Game::from_rle("2o$2o!").unwrap();
+        let mut tree = QuadTree::from_game(&block).unwrap();
+        tree.step(u64::MAX).unwrap();
+        assert_eq!(tree.get_generation(), u64::MAX);
+        assert_eq!(tree.to_game().get_sorted_cells(), block.get_sorted_cells());
+    }
+
+    #[test]
+    fn steps_games_on_the_plane_and_bounded_grids() {
+        let mut game = Game::from_rle("x = 3, y = 3\nbo$2bo$3o!").unwrap();
+        let mut torus = Game { topology: "T8,8".parse().unwrap(), ..game.clone() };
+        let mut expected = game.clone();
+        for _ in 0..40 {
+            expected.evolve();
+        }
+        game.step(40).unwrap();
+        assert_eq!(game.get_sorted_cells(), expected.get_sorted_cells());
+        torus.step(32).unwrap();
+        assert_eq!(torus.live_nodes.len(), 5);
+        assert_eq!(torus.topology, "T8,8".parse().unwrap());
+
+        let mut far = Game { live_nodes: vec![Node { x: MAX_DISTANCE - 1, y: 0 }], ..Game::new() };
+        far.live_nodes.push(Node { x: MAX_DISTANCE - 2, y: 0 });
+        let before = far.get_sorted_cells();
+        assert!(far.step(2).is_err());
+        assert_eq!(far.get_sorted_cells(), before);
+    }
+}
@@ -0,0 +1,137 @@
+use std::borrow::Cow;
+use std::time::Duration;
+use gif::{Encoder, Frame, Repeat};
+use crate::box_boundary::BoxBoundary;
+use crate::game::Game;
+use crate::render::{get_region_cells, get_region_size, ImageError, RenderOptions, Rgb};
+
+const DEAD: u8 = 0;
+const LIVE: u8 = 1;
+const TRAIL: u8 = 2;
+
+/// How a run of a game is animated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationOptions {
+    pub render: RenderOptions,
+    /// How long each generation is shown, rounded to hundredths of a second.
+    pub frame_delay: Duration,
+    /// Draws dead nodes that were alive in an earlier frame in this color.
+    pub trail_color: Option<Rgb>,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        AnimationOptions { render: RenderOptions::default(), frame_delay: Duration::from_millis(100), trail_color: None }
+    }
+}
+
+impl Game {
+    /// Animates `frames` generations of the game, starting with the current
+    /// one, as a looping GIF of the region. The game itself isn't evolved.
+    pub fn to_gif(&self, region: &BoxBoundary, frames: usize, options: &AnimationOptions) -> Result<Vec<u8>, ImageError> {
+        let (columns, rows) = get_region_size(region)?;
+        let cell_size = options.render.cell_size as usize;
+        let to_pixels = |nodes: usize| nodes.checked_mul(cell_size).and_then(|pixels| u16::try_from(pixels).ok());
+        let (width, height) = match (to_pixels(columns), to_pixels(rows)) {
+            (Some(width), Some(height)) if width > 0 && height > 0 => (width, height),
+            _ => return Err(ImageError::new(format!("cannot animate {}x{} nodes at {} pixels each", columns, rows, cell_size))),
+        };
+        let mut palette = Vec::with_capacity(9);
+        palette.extend_from_slice(&options.render.dead_color);
+        palette.extend_from_slice(&options.render.live_color);
+        palette.extend_from_slice(&options.trail_color.unwrap_or(options.render.dead_color));
+        let delay = (options.frame_delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = Encoder::new(&mut bytes, width, height, &palette)
+                .map_err(|error| ImageError::new(error.to_string()))?;
+            encoder.set_repeat(Repeat::Infinite).map_err(|error| ImageError::new(error.to_string()))?;
+            let mut game = self.clone();
+            let mut visited = vec![vec![false; columns]; rows];
+            for frame in 0..frames {
+                if frame > 0 {
+                    game.evolve();
+                }
+                let cells = get_region_cells(&game, region)?;
+                let mut buffer = Vec::with_capacity(width as usize * height as usize);
+                for (row, seen) in cells.iter().zip(visited.iter_mut()) {
+                    let line: Vec<u8> = row.iter()
+                        .zip(seen.iter_mut())
+                        .flat_map(|(&alive, seen)| {
+                            let index = match (alive, *seen && options.trail_color.is_some()) {
+                                (true, _) => LIVE,
+                                (false, true) => TRAIL,
+                                (false, false) => DEAD,
+                            };
+                            *seen |= alive;
+                            std::iter::repeat_n(index, cell_size)
+                        })
+                        .collect();
+                    for _ in 0..cell_size {
+                        buffer.extend_from_slice(&line);
+                    }
+                }
+                let frame = Frame { width, height, delay, buffer: Cow::Owned(buffer), ..Frame::default() };
+                encoder.write_frame(&frame).map_err(|error| ImageError::new(error.to_string()))?;
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use gif::{ColorOutput, DecodeOptions};
+    use crate::node::{Coord, Node};
+
+    fn decode_frames(bytes: &[u8]) -> Vec<(u16, Vec<u8>)> {
+        let mut options = DecodeOptions::new();
+        options.set_color_output(ColorOutput::Indexed);
+        let mut decoder = options.read_info(bytes).unwrap();
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            frames.push((frame.delay, frame.buffer.to_vec()));
+        }
+        frames
+    }
+
+    fn region(x0: Coord, y0: Coord, x1: Coord, y1: Coord) -> BoxBoundary {
+        BoxBoundary { lower: Node { x: x0, y: y0 }, upper: Node { x: x1, y: y1 } }
+    }
+
+    #[test]
+    fn animates_each_generation() {
+        let blinker = Game::from_rle("3o!").unwrap();
+        let options = AnimationOptions { frame_delay: Duration::from_millis(250), ..AnimationOptions::default() };
+        let bytes = blinker.to_gif(&region(0, -1, 2, 1), 3, &options).unwrap();
+        let frames = decode_frames(&bytes);
+        assert_eq!(frames.len(), 3);
+        assert!(frames.iter().all(|(delay, buffer)| *delay == 25 && buffer.len() == 12 * 12));
+        assert_eq!(frames[0].1, frames[2].1);
+        assert_ne!(frames[0].1, frames[1].1);
+        assert_eq!(blinker.live_nodes.len(), 3);
+    }
+
+    #[test]
+    fn draws_trails_behind_moving_nodes() {
+        let glider = Game::from_rle("bo$2bo$3o!").unwrap();
+        let options = AnimationOptions {
+            render: RenderOptions { cell_size: 1, ..RenderOptions::default() },
+            trail_color: Some([0, 0, 255]),
+            ..AnimationOptions::default()
+        };
+        let frames = decode_frames(&glider.to_gif(&region(0, -4, 6, 2), 5, &options).unwrap());
+        assert!(!frames[0].1.contains(&TRAIL));
+        assert!(frames[4].1.contains(&TRAIL));
+        assert_eq!(frames[4].1.iter().filter(|&&index| index == LIVE).count(), 5);
+        let plain = decode_frames(&glider.to_gif(&region(0, -4, 6, 2), 5, &AnimationOptions::default()).unwrap());
+        assert!(plain.iter().all(|(_, buffer)| !buffer.contains(&TRAIL)));
+    }
+
+    #[test]
+    fn rejects_frames_too_large_for_a_gif() {
+        let glider = Game::from_rle("bo$2bo$3o!").unwrap();
+        assert!(glider.to_gif(&region(0, 0, 20000, 0), 1, &AnimationOptions::default()).is_err());
+    }
+}
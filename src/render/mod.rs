@@ -0,0 +1,64 @@
+use std::fmt;
+use crate::box_boundary::BoxBoundary;
+use crate::game::Game;
+use crate::node::Coord;
+
+pub mod png;
+pub mod gif;
+pub mod svg;
+pub mod text;
+
+pub type Rgb = [u8; 3];
+
+/// How a region of a game is drawn to an image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    /// The side of one node, in pixels.
+    pub cell_size: u32,
+    pub live_color: Rgb,
+    pub dead_color: Rgb,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions { cell_size: 4, live_color: [255, 255, 255], dead_color: [0, 0, 0] }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ImageError(String);
+
+impl ImageError {
+    pub fn new(message: impl Into<String>) -> Self {
+        ImageError(message.into())
+    }
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "image error: {}", self.0)
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+/// The width and height in nodes of a region, if it fits in an image.
+pub fn get_region_size(region: &BoxBoundary) -> Result<(usize, usize), ImageError> {
+    let size = |lower: Coord, upper: Coord| {
+        upper.checked_sub(lower)
+            .and_then(|size| usize::try_from(size).ok())
+            .and_then(|size| size.checked_add(1))
+            .ok_or_else(|| ImageError::new("the region is too large for an image"))
+    };
+    Ok((size(region.lower.x, region.upper.x)?, size(region.lower.y, region.upper.y)?))
+}
+
+/// Which nodes of the region are alive, row by row from the top.
+pub fn get_region_cells(game: &Game, region: &BoxBoundary) -> Result<Vec<Vec<bool>>, ImageError> {
+    let (width, height) = get_region_size(region)?;
+    let mut cells = vec![vec![false; width]; height];
+    for node in game.live_nodes.iter().filter(|node| region.contains(node)) {
+        cells[(region.upper.y - node.y) as usize][(node.x - region.lower.x) as usize] = true;
+    }
+    Ok(cells)
+}
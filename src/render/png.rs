@@ -0,0 +1,150 @@
+use png::{BitDepth, ColorType, Decoder, Encoder, Transformations};
+use crate::box_boundary::BoxBoundary;
+use crate::game::Game;
+use crate::node::{Coord, Node};
+use crate::render::{get_region_cells, get_region_size, ImageError, RenderOptions};
+
+/// How the pixels of an image become live nodes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportOptions {
+    /// Squares of `scale` by `scale` pixels become one node.
+    pub scale: u32,
+    /// Squares darker than this brightness, from 0 to 255, are alive.
+    pub threshold: u8,
+    /// Makes the squares lighter than the threshold alive instead.
+    pub invert: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        ImportOptions { scale: 1, threshold: 128, invert: false }
+    }
+}
+
+/// The brightness of each pixel, transparent pixels counting as white.
+fn get_brightness(bytes: &[u8]) -> Result<(usize, usize, Vec<u8>), ImageError> {
+    let mut decoder = Decoder::new(bytes);
+    decoder.set_transformations(Transformations::EXPAND | Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|error| ImageError::new(error.to_string()))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).map_err(|error| ImageError::new(error.to_string()))?;
+    let channels = info.color_type.samples();
+    let brightness = buffer[..info.buffer_size()]
+        .chunks(channels)
+        .map(|pixel| {
+            let (luma, alpha) = match info.color_type {
+                ColorType::Grayscale => (pixel[0] as u32, 255),
+                ColorType::GrayscaleAlpha => (pixel[0] as u32, pixel[1] as u32),
+                ColorType::Rgb => ((299 * pixel[0] as u32 + 587 * pixel[1] as u32 + 114 * pixel[2] as u32) / 1000, 255),
+                _ => ((299 * pixel[0] as u32 + 587 * pixel[1] as u32 + 114 * pixel[2] as u32) / 1000, pixel[3] as u32),
+            };
+            ((luma * alpha + 255 * (255 - alpha)) / 255) as u8
+        })
+        .collect();
+    Ok((info.width as usize, info.height as usize, brightness))
+}
+
+impl Game {
+    /// Seeds a game from a PNG image, dark pixels on a light background
+    /// becoming live nodes. Its bottom left corner is at the origin.
+    pub fn from_png(bytes: &[u8], options: &ImportOptions) -> Result<Game, ImageError> {
+        if options.scale == 0 {
+            return Err(ImageError::new("the scale must be at least 1"));
+        }
+        let (width, height, brightness) = get_brightness(bytes)?;
+        let scale = options.scale as usize;
+        let (columns, rows) = (width.div_ceil(scale), height.div_ceil(scale));
+        let mut game = Game::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                let pixels: Vec<u32> = (row * scale..height.min((row + 1) * scale))
+                    .flat_map(|y| (column * scale..width.min((column + 1) * scale)).map(move |x| (x, y)))
+                    .map(|(x, y)| brightness[y * width + x] as u32)
+                    .collect();
+                let average = pixels.iter().sum::<u32>() / pixels.len() as u32;
+                if (average < options.threshold as u32) != options.invert {
+                    game.live_nodes.push(Node { x: column as Coord, y: (rows - 1 - row) as Coord });
+                }
+            }
+        }
+        Ok(game)
+    }
+
+    /// Draws a region of the game to a PNG image, without any window.
+    pub fn to_png(&self, region: &BoxBoundary, options: &RenderOptions) -> Result<Vec<u8>, ImageError> {
+        let (columns, rows) = get_region_size(region)?;
+        let cell_size = options.cell_size as usize;
+        let to_pixels = |nodes: usize| nodes.checked_mul(cell_size).and_then(|pixels| u32::try_from(pixels).ok());
+        let (width, height) = match (to_pixels(columns), to_pixels(rows)) {
+            (Some(width), Some(height)) if width > 0 && height > 0 => (width, height),
+            _ => return Err(ImageError::new(format!("cannot draw {}x{} nodes at {} pixels each", columns, rows, cell_size))),
+        };
+        let cells = get_region_cells(self, region)?;
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 3);
+        for row in &cells {
+            let line: Vec<u8> = row.iter()
+                .flat_map(|&alive| {
+                    let color = if alive { options.live_color } else { options.dead_color };
+                    std::iter::repeat_n(color, cell_size).flatten()
+                })
+                .collect();
+            for _ in 0..cell_size {
+                pixels.extend_from_slice(&line);
+            }
+        }
+        let mut bytes = Vec::new();
+        let mut encoder = Encoder::new(&mut bytes, width, height);
+        encoder.set_color(ColorType::Rgb);
+        encoder.set_depth(BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|error| ImageError::new(error.to_string()))?;
+        writer.write_image_data(&pixels).map_err(|error| ImageError::new(error.to_string()))?;
+        writer.finish().map_err(|error| ImageError::new(error.to_string()))?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn glider() -> Game {
+        Game::from_rle("bo$2bo$3o!").unwrap()
+    }
+
+    fn region(x0: Coord, y0: Coord, x1: Coord, y1: Coord) -> BoxBoundary {
+        BoxBoundary { lower: Node { x: x0, y: y0 }, upper: Node { x: x1, y: y1 } }
+    }
+
+    #[test]
+    fn round_trips_game_through_png() {
+        let game = glider();
+        let options = RenderOptions { cell_size: 3, live_color: [0, 0, 0], dead_color: [255, 255, 255] };
+        let png = game.to_png(&game.get_live_boundary().unwrap(), &options).unwrap();
+        let copy = Game::from_png(&png, &ImportOptions { scale: 3, ..ImportOptions::default() }).unwrap();
+        assert_eq!(copy.get_sorted_cells(), game.get_sorted_cells());
+        let pixels = Game::from_png(&png, &ImportOptions::default()).unwrap();
+        assert_eq!(pixels.live_nodes.len(), 5 * 9);
+    }
+
+    #[test]
+    fn draws_the_region_at_the_cell_size() {
+        let png = glider().to_png(&region(-2, -2, 5, 5), &RenderOptions::default()).unwrap();
+        let reader = Decoder::new(png.as_slice()).read_info().unwrap();
+        let info = reader.info().clone();
+        assert_eq!((info.width, info.height), (32, 32));
+        let inverted = Game::from_png(&png, &ImportOptions { scale: 4, invert: true, ..ImportOptions::default() }).unwrap();
+        assert_eq!(inverted.get_sorted_cells(), Game {
+            live_nodes: glider().live_nodes.iter().map(|node| Node { x: node.x + 2, y: node.y + 2 }).collect(),
+            ..Game::new()
+        }.get_sorted_cells());
+    }
+
+    #[test]
+    fn rejects_unusable_images_and_options() {
+        assert!(Game::from_png(b"not a png", &ImportOptions::default()).is_err());
+        let png = glider().to_png(&region(0, 0, 0, 0), &RenderOptions::default()).unwrap();
+        assert!(Game::from_png(&png, &ImportOptions { scale: 0, ..ImportOptions::default() }).is_err());
+        assert!(glider().to_png(&region(0, 0, 0, 0), &RenderOptions { cell_size: 0, ..RenderOptions::default() }).is_err());
+        assert!(glider().to_png(&region(Coord::MIN, 0, Coord::MAX, 0), &RenderOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt::Write;
+use crate::box_boundary::BoxBoundary;
+use crate::game::Game;
+use crate::node::Coord;
+use crate::render::{get_region_size, ImageError, RenderOptions, Rgb};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellShape {
+    Square,
+    Circle,
+}
+
+/// How a game is drawn as an SVG image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    pub render: RenderOptions,
+    pub shape: CellShape,
+    /// Draws lines between nodes in this color.
+    pub grid_color: Option<Rgb>,
+    /// Labels the rows and columns whose coordinate is a multiple of this.
+    pub label_step: Option<Coord>,
+    /// Overlays this many generations, the later ones fainter and beneath.
+    pub generations: usize,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            render: RenderOptions::default(),
+            shape: CellShape::Square,
+            grid_color: None,
+            label_step: None,
+            generations: 1,
+        }
+    }
+}
+
+fn to_hex(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+/// Path data for the live nodes of the region, one subpath per horizontal
+/// run of squares or one arc pair per circle.
+fn get_path(game: &Game, region: &BoxBoundary, shape: CellShape) -> String {
+    let mut cells: Vec<(Coord, Coord)> = game.live_nodes.iter()
+        .filter(|node| region.contains(node))
+        .map(|node| (region.upper.y - node.y, node.x - region.lower.x))
+        .collect();
+    cells.sort();
+    cells.dedup();
+    let mut path = String::new();
+    match shape {
+        CellShape::Square => {
+            let mut runs: Vec<(Coord, Coord, Coord)> = Vec::new();
+            for (row, column) in cells {
+                match runs.last_mut() {
+                    Some((last_row, start, length)) if *last_row == row && *start + *length == column => *length += 1,
+                    _ => runs.push((row, column, 1)),
+                }
+            }
+            for (row, column, length) in runs {
+                write!(path, "M{} {}h{}v1h-{}z", column, row, length, length).unwrap();
+            }
+        }
+        CellShape::Circle => {
+            for (row, column) in cells {
+                write!(path, "M{}.1 {}.5a.4 .4 0 1 0 .8 0a.4 .4 0 1 0-.8 0", column, row).unwrap();
+            }
+        }
+    }
+    path
+}
+
+impl Game {
+    /// Draws the game as an SVG image of the region, or of its live nodes
+    /// when there is none. Nodes are one unit wide, scaled to the cell size.
+    pub fn to_svg(&self, region: Option<&BoxBoundary>, options: &SvgOptions) -> Result<String, ImageError> {
+        let region = region.cloned()
+            .or_else(|| self.get_live_boundary())
+            .unwrap_or_else(BoxBoundary::new);
+        let (columns, rows) = get_region_size(&region)?;
+        let margin = if options.label_step.is_some() { 3 } else { 0 };
+        let cell_size = options.render.cell_size as usize;
+        let to_pixels = |nodes: usize| nodes.checked_add(margin)
+            .and_then(|nodes| nodes.checked_mul(cell_size))
+            .and_then(|pixels| u32::try_from(pixels).ok());
+        let (width, height) = match (to_pixels(columns), to_pixels(rows)) {
+            (Some(width), Some(height)) if width > 0 && height > 0 => (width, height),
+            _ => return Err(ImageError::new(format!("cannot draw {}x{} nodes at {} pixels each", columns, rows, cell_size))),
+        };
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\">\n",
+            width, height, -(margin as isize), -(margin as isize), columns + margin, rows + margin,
+        );
+        writeln!(svg, "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>", columns, rows, to_hex(options.render.dead_color)).unwrap();
+        let generations = options.generations.max(1);
+        let mut phases = vec![self.clone()];
+        for _ in 1..generations {
+            let mut next = phases[phases.len() - 1].clone();
+            next.evolve();
+            phases.push(next);
+        }
+        let fill = to_hex(options.render.live_color);
+        for (age, phase) in phases.iter().enumerate().rev() {
+            let path = get_path(phase, &region, options.shape);
+            if path.is_empty() {
+                continue;
+            }
+            if age == 0 {
+                writeln!(svg, "<path fill=\"{}\" d=\"{}\"/>", fill, path).unwrap();
+            } else {
+                let opacity = (generations - age) as f64 / (generations + 1) as f64;
+                writeln!(svg, "<path fill=\"{}\" fill-opacity=\"{:.2}\" d=\"{}\"/>", fill, opacity, path).unwrap();
+            }
+        }
+        if let Some(color) = options.grid_color {
+            let mut path = String::new();
+            for column in 0..=columns {
+                write!(path, "M{} 0v{}", column, rows).unwrap();
+            }
+            for row in 0..=rows {
+                write!(path, "M0 {}h{}", row, columns).unwrap();
+            }
+            writeln!(svg, "<path stroke=\"{}\" stroke-width=\"1\" vector-effect=\"non-scaling-stroke\" fill=\"none\" d=\"{}\"/>", to_hex(color), path).unwrap();
+        }
+        if let Some(step) = options.label_step.filter(|&step| step > 0) {
+            svg.push_str("<g font-size=\".8\" font-family=\"monospace\" fill=\"currentColor\">\n");
+            for (column, x) in (region.lower.x..=region.upper.x).enumerate().filter(|(_, x)| x % step == 0) {
+                writeln!(svg, "<text x=\"{}.5\" y=\"-.5\" text-anchor=\"middle\">{}</text>", column, x).unwrap();
+            }
+            for (row, y) in (region.lower.y..=region.upper.y).rev().enumerate().filter(|(_, y)| y % step == 0) {
+                writeln!(svg, "<text x=\"-.3\" y=\"{}.8\" text-anchor=\"end\">{}</text>", row, y).unwrap();
+            }
+            svg.push_str("</g>\n");
+        }
+        svg.push_str("</svg>\n");
+        Ok(svg)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::node::Node;
+
+    fn count(svg: &str, pattern: &str) -> usize {
+        svg.matches(pattern).count()
+    }
+
+    #[test]
+    fn merges_runs_of_squares_into_one_path() {
+        let svg = Game::from_rle("3o$obo!").unwrap().to_svg(None, &SvgOptions::default()).unwrap();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"12\" height=\"8\" viewBox=\"0 0 3 2\">"));
+        assert_eq!(count(&svg, "<path"), 1);
+        assert!(svg.contains("d=\"M0 0h3v1h-3zM0 1h1v1h-1zM2 1h1v1h-1z\""));
+        assert!(svg.contains("<rect width=\"3\" height=\"2\" fill=\"#000000\"/>"));
+    }
+
+    #[test]
+    fn draws_circles_in_the_given_region() {
+        let region = BoxBoundary { lower: Node { x: -1, y: -1 }, upper: Node { x: 3, y: 1 } };
+        let options = SvgOptions { shape: CellShape::Circle, ..SvgOptions::default() };
+        let svg = Game::from_rle("3o!").unwrap().to_svg(Some(&region), &options).unwrap();
+        assert!(svg.contains("viewBox=\"0 0 5 3\""));
+        assert_eq!(count(&svg, "a.4 .4 0 1 0 .8 0"), 3);
+        assert!(svg.contains("M1.1 1.5a"));
+    }
+
+    #[test]
+    fn draws_grid_lines_and_labels() {
+        let options = SvgOptions { grid_color: Some([128, 128, 128]), label_step: Some(2), ..SvgOptions::default() };
+        let svg = Game::from_rle("3o!").unwrap().to_svg(None, &options).unwrap();
+        assert!(svg.contains("viewBox=\"-3 -3 6 4\""));
+        assert!(svg.contains("stroke=\"#808080\""));
+        assert_eq!(count(&svg, "M0 0v1"), 1);
+        assert_eq!(count(&svg, "<text"), 3);
+        assert!(svg.contains(">2</text>"));
+    }
+
+    #[test]
+    fn overlays_later_generations_beneath() {
+        let region = BoxBoundary { lower: Node { x: 0, y: -1 }, upper: Node { x: 2, y: 1 } };
+        let options = SvgOptions { generations: 2, ..SvgOptions::default() };
+        let svg = Game::from_rle("3o!").unwrap().to_svg(Some(&region), &options).unwrap();
+        let faint = svg.find("fill-opacity=\"0.33\" d=\"M1 0h1v1h-1zM1 1h1v1h-1zM1 2h1v1h-1z\"").unwrap();
+        let solid = svg.find("d=\"M0 1h3v1h-3z\"").unwrap();
+        assert!(faint < solid);
+    }
+
+    #[test]
+    fn rejects_images_too_large_to_draw() {
+        let region = BoxBoundary { lower: Node { x: Coord::MIN, y: 0 }, upper: Node { x: Coord::MAX, y: 0 } };
+        assert!(Game::new().to_svg(Some(&region), &SvgOptions::default()).is_err());
+        let region = BoxBoundary { lower: Node { x: 0, y: 0 }, upper: Node { x: 1 << 30, y: 0 } };
+        assert!(Game::new().to_svg(Some(&region), &SvgOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::box_boundary::BoxBoundary;
+use crate::game::Game;
+use crate::render::{get_region_cells, ImageError};
+
+/// How nodes are packed into terminal characters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextStyle {
+    /// One column by two rows of nodes per character, drawn with `▀`, `▄` and `█`.
+    HalfBlock,
+    /// Two columns by four rows of nodes per character, drawn with braille dots.
+    Braille,
+}
+
+impl TextStyle {
+    /// How many columns and rows of nodes one character holds.
+    pub fn get_cell_size(&self) -> (usize, usize) {
+        match self {
+            TextStyle::HalfBlock => (1, 2),
+            TextStyle::Braille => (2, 4),
+        }
+    }
+}
+
+/// The braille dot for each node of a 2x4 character, by row then column.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Draws the region of the game as lines of text, the top row first.
+pub fn to_text(game: &Game, region: &BoxBoundary, style: TextStyle) -> Result<Vec<String>, ImageError> {
+    let cells = get_region_cells(game, region)?;
+    let width = cells.first().map_or(0, Vec::len);
+    let (columns, rows) = style.get_cell_size();
+    let is_alive = |row: usize, column: usize| cells.get(row).is_some_and(|cells| cells.get(column) == Some(&true));
+    Ok((0..cells.len().div_ceil(rows))
+        .map(|line| {
+            (0..width.div_ceil(columns))
+                .map(|character| {
+                    let (row, column) = (line * rows, character * columns);
+                    match style {
+                        TextStyle::HalfBlock => match (is_alive(row, column), is_alive(row + 1, column)) {
+                            (true, true) => '█',
+                            (true, false) => '▀',
+                            (false, true) => '▄',
+                            (false, false) => ' ',
+                        },
+                        TextStyle::Braille => {
+                            let mut dots = 0;
+                            for (dy, row_dots) in BRAILLE_DOTS.iter().enumerate() {
+                                for (dx, dot) in row_dots.iter().enumerate() {
+                                    if is_alive(row + dy, column + dx) {
+                                        dots |= dot;
+                                    }
+                                }
+                            }
+                            char::from_u32(0x2800 + dots).unwrap_or(' ')
+                        }
+                    }
+                })
+                .collect()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::node::Node;
+
+    fn glider() -> Game {
+        Game::from_rle("bo$2bo$3o!").unwrap()
+    }
+
+    #[test]
+    fn draws_half_blocks() {
+        let region = BoxBoundary { lower: Node { x: 0, y: -1 }, upper: Node { x: 2, y: 2 } };
+        assert_eq!(to_text(&glider(), &region, TextStyle::HalfBlock).unwrap(), vec![" ▀▄", "▀▀▀"]);
+    }
+
+    #[test]
+    fn draws_braille_dots() {
+        let region = BoxBoundary { lower: Node { x: 0, y: -1 }, upper: Node { x: 3, y: 2 } };
+        assert_eq!(to_text(&glider(), &region, TextStyle::Braille).unwrap(), vec!["⠬⠆"]);
+        let odd = BoxBoundary { lower: Node { x: 0, y: 0 }, upper: Node { x: 2, y: 2 } };
+        assert_eq!(to_text(&glider(), &odd, TextStyle::Braille).unwrap(), vec!["⠬⠆"]);
+    }
+}
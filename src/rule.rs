@@ -0,0 +1,141 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A life-like rule: which live neighbor counts give birth to a dead node and
+/// which let a live node survive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub birth: [bool; 9],
+    pub survival: [bool; 9],
+}
+
+impl Rule {
+    pub fn conway() -> Self {
+        "B3/S23".parse().unwrap()
+    }
+
+    pub fn is_born(&self, live_neighbors: usize) -> bool {
+        self.birth.get(live_neighbors).copied().unwrap_or(false)
+    }
+
+    pub fn survives(&self, live_neighbors: usize) -> bool {
+        self.survival.get(live_neighbors).copied().unwrap_or(false)
+    }
+
+    /// The classic `23/3` survival/birth notation used by older formats.
+    pub fn to_survival_birth_string(&self) -> String {
+        format!("{}/{}", neighbor_counts(&self.survival), neighbor_counts(&self.birth))
+    }
+}
+
+fn neighbor_counts(neighbors: &[bool; 9]) -> String {
+    (0..9).filter(|&count| neighbors[count]).map(|count| count.to_string()).collect()
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::conway()
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "B{}/S{}", neighbor_counts(&self.birth), neighbor_counts(&self.survival))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseRuleError(String);
+
+impl fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid rule: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseRuleError {}
+
+/// Parses `B3/S23` style rules, in either order and any case, as well as the
+/// classic `23/3` survival/birth notation. B0 rules are rejected: they would
+/// bring the whole unbounded plane to life, which the engine can't represent.
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseRuleError(s.to_string());
+        let counts = |digits: &str| -> Result<[bool; 9], ParseRuleError> {
+            let mut neighbors = [false; 9];
+            for digit in digits.chars() {
+                match digit.to_digit(10) {
+                    Some(count) if count < 9 => neighbors[count as usize] = true,
+                    _ => return Err(error()),
+                }
+            }
+            Ok(neighbors)
+        };
+        let (first, second) = s.trim().split_once('/').ok_or_else(error)?;
+        let (first, second) = (first.to_ascii_uppercase(), second.to_ascii_uppercase());
+        let (birth, survival) = match (first.strip_prefix('B'), second.strip_prefix('S')) {
+            (Some(birth), Some(survival)) => (birth, survival),
+            _ => match (first.strip_prefix('S'), second.strip_prefix('B')) {
+                (Some(survival), Some(birth)) => (birth, survival),
+                _ => (second.as_str(), first.as_str()),
+            },
+        };
+        let rule = Rule { birth: counts(birth)?, survival: counts(survival)? };
+        if rule.birth[0] {
+            return Err(ParseRuleError(format!("{} (B0 rules are not supported)", s)));
+        }
+        Ok(rule)
+    }
+}
+
+/// Rules are saved in their `B3/S23` notation.
+impl Serialize for Rule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn conway_is_born_with_3_and_survives_with_2_or_3() {
+        let rule = Rule::conway();
+        assert!(rule.is_born(3));
+        assert!(!rule.is_born(2));
+        assert!(rule.survives(2));
+        assert!(rule.survives(3));
+        assert!(!rule.survives(4));
+        assert!(!rule.survives(9));
+    }
+
+    #[test]
+    fn parses_rule_notations() {
+        let high_life = Rule::from_str("B36/S23").unwrap();
+        assert_eq!(Rule::from_str("b36/s23").unwrap(), high_life);
+        assert_eq!(Rule::from_str("S23/B36").unwrap(), high_life);
+        assert_eq!(Rule::from_str("23/36").unwrap(), high_life);
+        assert_eq!(high_life.to_string(), "B36/S23");
+        assert_eq!(high_life.to_survival_birth_string(), "23/36");
+        assert_eq!(Rule::from_str("B/S").unwrap().to_string(), "B/S");
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        assert!(Rule::from_str("B3S23").is_err());
+        assert!(Rule::from_str("B39/S23").is_err());
+        assert!(Rule::from_str("Bx/S23").is_err());
+        assert!(Rule::from_str("B03/S23").is_err());
+        assert!(Rule::from_str("23/0").is_err());
+    }
+}
@@ -0,0 +1,279 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+use rhai::{Dynamic, Engine, EvalAltResult, Map, INT};
+use crate::game::Game;
+use crate::node::{Coord, Node};
+use crate::rule::Rule;
+use crate::topology::Topology;
+
+/// What a script works on: the game, how many generations it has evolved and
+/// where the camera looks, in nodes rather than world units.
+#[derive(Clone)]
+pub struct ScriptState {
+    pub game: Game,
+    pub generation: u64,
+    pub camera_x: f64,
+    pub camera_y: f64,
+    pub zoom: f64,
+}
+
+impl ScriptState {
+    pub fn new(game: Game) -> Self {
+        ScriptState { game, generation: 0, camera_x: 0., camera_y: 0., zoom: 1. }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ScriptError(String);
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "script error: {}", self.0)
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// How many operations a script may run before it's stopped, so a script that
+/// never ends fails instead of hanging whatever runs it.
+pub const MAX_OPERATIONS: u64 = 10_000_000;
+
+/// How many generations a script may evolve one at a time, in all, which it
+/// does on bounded grids. The operation limit doesn't count them, so without
+/// this a single `evolve` call could still hang.
+pub const MAX_SINGLE_GENERATIONS: u64 = 1_000_000;
+
+/// Evolves the game, taking the generations evolved one at a time out of
+/// what is left of `MAX_SINGLE_GENERATIONS`.
+fn evolve(state: &mut ScriptState, generations: INT, budget: &Cell<u64>) -> ScriptResult<()> {
+    if generations < 0 {
+        return Err(format!("can't evolve {} generations", generations).into());
+    }
+    if state.game.topology != Topology::Plane || generations == 1 {
+        let left = budget.get();
+        if generations as u64 > left {
+            return Err(format!("can't evolve more than {} generations one at a time", MAX_SINGLE_GENERATIONS).into());
+        }
+        budget.set(left - generations as u64);
+    }
+    state.game.step(generations as u64).map_err(|error| error.to_string())?;
+    state.generation = state.generation.saturating_add(generations as u64);
+    Ok(())
+}
+
+/// Adds the pattern to the game with its top left node at x, y.
+fn place(state: &mut ScriptState, pattern: &str, x: INT, y: INT) -> ScriptResult<()> {
+    let pattern = Game::from_pattern(pattern).map_err(|error| error.to_string())?;
+    let top = pattern.live_nodes.iter().map(|node| node.y).max().unwrap_or(0);
+    let left = pattern.live_nodes.iter().map(|node| node.x).min().unwrap_or(0);
+    for node in pattern.live_nodes {
+        let node = node.x.checked_sub(left).and_then(|dx| dx.checked_add(x as Coord))
+            .zip(node.y.checked_sub(top).and_then(|dy| dy.checked_add(y as Coord)))
+            .map(|(x, y)| Node { x, y })
+            .ok_or_else(|| format!("the pattern doesn't fit at {}, {}", x, y))?;
+        if !state.game.is_node_alive(node.x, node.y) {
+            state.game.toggle(&node);
+        }
+    }
+    Ok(())
+}
+
+/// A script integer, which is 64 bits even with `wide-coordinates`.
+// The coordinate is already an INT unless `wide-coordinates` is on.
+#[allow(clippy::useless_conversion)]
+fn to_int(value: Coord) -> ScriptResult<INT> {
+    INT::try_from(value).map_err(|_| format!("{} is too large for a script", value).into())
+}
+
+fn get_bounds(state: &ScriptState) -> ScriptResult<Dynamic> {
+    match state.game.get_live_boundary() {
+        Some(boundary) => {
+            let mut bounds = Map::new();
+            bounds.insert("left".into(), to_int(boundary.lower.x)?.into());
+            bounds.insert("bottom".into(), to_int(boundary.lower.y)?.into());
+            bounds.insert("right".into(), to_int(boundary.upper.x)?.into());
+            bounds.insert("top".into(), to_int(boundary.upper.y)?.into());
+            Ok(bounds.into())
+        }
+        None => Ok(Dynamic::UNIT),
+    }
+}
+
+fn get_camera(state: &ScriptState) -> Map {
+    let mut camera = Map::new();
+    camera.insert("x".into(), state.camera_x.into());
+    camera.insert("y".into(), state.camera_y.into());
+    camera.insert("zoom".into(), state.zoom.into());
+    camera
+}
+
+/// An engine whose functions work on the shared state. Coordinates are 64 bit
+/// integers, y up as in `Game`. `print` writes to stderr, so it doesn't mix
+/// with patterns written to stdout.
+fn create_engine(state: &Rc<RefCell<ScriptState>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.on_print(|text| eprintln!("{}", text));
+    let budget = Rc::new(Cell::new(MAX_SINGLE_GENERATIONS));
+    let (s, b) = (state.clone(), budget.clone());
+    engine.register_fn("evolve", move || evolve(&mut s.borrow_mut(), 1, &b));
+    let s = state.clone();
+    engine.register_fn("evolve", move |generations: INT| evolve(&mut s.borrow_mut(), generations, &budget));
+    let s = state.clone();
+    engine.register_fn("toggle", move |x: INT, y: INT| {
+        s.borrow_mut().game.toggle(&Node { x: x as Coord, y: y as Coord })
+    });
+    let s = state.clone();
+    engine.register_fn("is_node_alive", move |x: INT, y: INT| {
+        s.borrow().game.is_node_alive(x as Coord, y as Coord)
+    });
+    let s = state.clone();
+    engine.register_fn("set_node", move |x: INT, y: INT, alive: bool| {
+        let mut state = s.borrow_mut();
+        if state.game.is_node_alive(x as Coord, y as Coord) != alive {
+            state.game.toggle(&Node { x: x as Coord, y: y as Coord });
+        }
+    });
+    let s = state.clone();
+    engine.register_fn("clear", move || s.borrow_mut().game.live_nodes.clear());
+    let s = state.clone();
+    engine.register_fn("place", move |pattern: &str, x: INT, y: INT| place(&mut s.borrow_mut(), pattern, x, y));
+    let s = state.clone();
+    engine.register_fn("place", move |pattern: &str| place(&mut s.borrow_mut(), pattern, 0, 0));
+    let s = state.clone();
+    engine.register_fn("to_rle", move || -> ScriptResult<String> {
+        s.borrow().game.to_rle().map_err(|error| error.to_string().into())
+    });
+    let s = state.clone();
+    engine.register_fn("population", move || s.borrow().game.live_nodes.len() as INT);
+    let s = state.clone();
+    engine.register_fn("generation", move || s.borrow().generation as INT);
+    let s = state.clone();
+    engine.register_fn("bounds", move || get_bounds(&s.borrow()));
+    let s = state.clone();
+    engine.register_fn("rule", move || s.borrow().game.rule.to_string());
+    let s = state.clone();
+    engine.register_fn("set_rule", move |rule: &str| -> ScriptResult<()> {
+        s.borrow_mut().game.rule = rule.parse::<Rule>().map_err(|error| error.to_string())?;
+        Ok(())
+    });
+    let s = state.clone();
+    engine.register_fn("camera", move || get_camera(&s.borrow()));
+    let s = state.clone();
+    engine.register_fn("set_camera", move |x: f64, y: f64| {
+        let mut state = s.borrow_mut();
+        state.camera_x = x;
+        state.camera_y = y;
+    });
+    let s = state.clone();
+    engine.register_fn("set_camera", move |x: INT, y: INT| {
+        let mut state = s.borrow_mut();
+        state.camera_x = x as f64;
+        state.camera_y = y as f64;
+    });
+    let s = state.clone();
+    engine.register_fn("set_zoom", move |zoom: f64| -> ScriptResult<()> {
+        if zoom.is_nan() || zoom <= 0. {
+            return Err(format!("invalid zoom: {}", zoom).into());
+        }
+        s.borrow_mut().zoom = zoom;
+        Ok(())
+    });
+    engine
+}
+
+/// Runs the script on the state, returning it as the script left it.
+pub fn run_script(script: &str, state: ScriptState) -> Result<ScriptState, ScriptError> {
+    let state = Rc::new(RefCell::new(state));
+    let engine = create_engine(&state);
+    engine.run(script).map_err(|error| ScriptError(error.to_string()))?;
+    drop(engine);
+    let state = match Rc::try_unwrap(state) {
+        Ok(state) => state.into_inner(),
+        Err(state) => state.borrow().clone(),
+    };
+    Ok(state)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builds_and_evolves_patterns() {
+        let script = r#"
+            toggle(0, 0); toggle(1, 0); toggle(2, 0);
+            if !is_node_alive(1, 0) || population() != 3 { throw "not built"; }
+            evolve();
+            let b = bounds();
+            if b.left != 1 || b.right != 1 || b.bottom != -1 || b.top != 1 { throw "not evolved"; }
+            place("bo$2bo$3o!", 10, 10);
+            evolve(8);
+        "#;
+        let state = run_script(script, ScriptState::new(Game::new())).unwrap();
+        assert_eq!(state.generation, 9);
+        assert_eq!(state.game.get_sorted_cells(), vec![(1, -1), (1, 0), (1, 1), (12, 6), (13, 6), (13, 8), (14, 6), (14, 7)]);
+    }
+
+    #[test]
+    fn sets_the_rule_and_camera() {
+        let script = r#"
+            set_rule("B36/S23");
+            set_camera(5, -3);
+            set_zoom(0.5);
+            if camera().x != 5.0 || rule() != "B36/S23" { throw "not set"; }
+            if bounds() != () { throw "not empty"; }
+        "#;
+        let state = run_script(script, ScriptState::new(Game::new())).unwrap();
+        assert_eq!(state.game.rule.to_string(), "B36/S23");
+        assert_eq!((state.camera_x, state.camera_y, state.zoom), (5., -3., 0.5));
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(run_script("set_rule(\"nonsense\")", ScriptState::new(Game::new())).is_err());
+        assert!(run_script("evolve(", ScriptState::new(Game::new())).is_err());
+        match run_script("throw \"stop\"", ScriptState::new(Game::new())) {
+            Err(error) => assert!(error.to_string().contains("stop")),
+            Ok(_) => panic!("the script should have failed"),
+        }
+    }
+
+    #[test]
+    fn stops_scripts_that_never_end() {
+        match run_script("toggle(0, 0); loop { }", ScriptState::new(Game::new())) {
+            Err(error) => assert!(error.to_string().contains("Too many operations"), "{}", error),
+            Ok(_) => panic!("the script should have been stopped"),
+        }
+    }
+
+    #[test]
+    fn limits_generations_evolved_one_at_a_time() {
+        let torus = Game::from_rle("x = 3, y = 1, rule = B3/S23:T8,8\n3o!").unwrap();
+        match run_script("evolve(1000000000000)", ScriptState::new(torus.clone())) {
+            Err(error) => assert!(error.to_string().contains("one at a time"), "{}", error),
+            Ok(_) => panic!("the script should have failed"),
+        }
+        assert_eq!(run_script("evolve(10); evolve(5)", ScriptState::new(torus)).unwrap().generation, 15);
+        assert_eq!(run_script("place(\"3o!\"); evolve(1000000000000)", ScriptState::new(Game::new())).unwrap().generation, 1_000_000_000_000);
+    }
+
+    #[test]
+    fn rejects_coordinates_out_of_range() {
+        let script = format!("place(\"2o!\", {}, 0)", INT::MAX);
+        #[cfg(not(feature = "wide-coordinates"))]
+        assert!(run_script(&script, ScriptState::new(Game::new())).is_err());
+        #[cfg(feature = "wide-coordinates")]
+        assert!(run_script(&script, ScriptState::new(Game::new())).is_ok());
+        let script = format!("place(\"2o!\", {}, 0)", INT::MAX - 1);
+        assert_eq!(run_script(&script, ScriptState::new(Game::new())).unwrap().game.live_nodes.len(), 2);
+        #[cfg(feature = "wide-coordinates")]
+        {
+            let game = Game { live_nodes: vec![Node { x: Coord::MAX, y: 0 }], ..Game::new() };
+            assert!(run_script("bounds()", ScriptState::new(game)).is_err());
+        }
+    }
+}
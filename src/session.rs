@@ -0,0 +1,278 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+#[cfg(feature = "gui")]
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::game::Game;
+use crate::grid::Grid;
+
+/// The schema version `Session::save` writes. Sessions of a newer version
+/// are rejected rather than read wrongly.
+pub const SESSION_VERSION: u64 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GameStatus {
+    Play,
+    Pause,
+}
+
+#[cfg_attr(feature = "gui", derive(Resource))]
+#[derive(Serialize, Deserialize)]
+pub struct GameState {
+    pub game: Game,
+    pub status: GameStatus,
+    pub grid: Grid,
+    /// How many generations the game has evolved.
+    #[serde(default)]
+    pub generation: u64,
+}
+
+impl GameState {
+    pub fn new(game: Game) -> Self {
+        GameState { game, status: GameStatus::Pause, grid: Grid::new(1000, 10), generation: 0 }
+    }
+
+    /// Saves the state as a session with the view and speed, without copying it.
+    pub fn save(&self, view: &View, speed: u64, path: impl AsRef<Path>) -> Result<(), SessionError> {
+        SessionRef { version: SESSION_VERSION, speed, view, state: self }.save(path.as_ref())
+    }
+}
+
+/// Where the camera looks, in world units, and its projection scale.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct View {
+    pub x: f32,
+    pub y: f32,
+    pub zoom: f32,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View { x: 0., y: 0., zoom: 1. }
+    }
+}
+
+/// Everything needed to resume a run: the game state, the view and the speed.
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub version: u64,
+    /// Milliseconds between generations while playing.
+    pub speed: u64,
+    pub view: View,
+    pub state: GameState,
+}
+
+/// A session borrowed from a running game, written the same way as `Session`.
+#[derive(Serialize)]
+struct SessionRef<'a> {
+    version: u64,
+    speed: u64,
+    view: &'a View,
+    state: &'a GameState,
+}
+
+impl SessionRef<'_> {
+    fn to_string(&self, format: SessionFormat) -> Result<String, SessionError> {
+        match format {
+            SessionFormat::Json => serde_json::to_string_pretty(self).map_err(|error| SessionError(error.to_string())),
+            SessionFormat::Toml => toml::to_string(self).map_err(|error| SessionError(error.to_string())),
+        }
+    }
+
+    fn save(&self, path: &Path) -> Result<(), SessionError> {
+        let text = self.to_string(SessionFormat::from_path(path)?)?;
+        fs::write(path, text).map_err(|error| SessionError(format!("{}: {}", path.display(), error)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionFormat {
+    Json,
+    Toml,
+}
+
+impl SessionFormat {
+    pub fn from_path(path: &Path) -> Result<SessionFormat, SessionError> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Ok(SessionFormat::Json),
+            Some("toml") => Ok(SessionFormat::Toml),
+            _ => Err(SessionError(format!("{} is not a .json or .toml file", path.display()))),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SessionError(String);
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid session: {}", self.0)
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+/// The upgrade from each version before `SESSION_VERSION` to the next, the
+/// first one from version 1. A new schema version adds its upgrade here.
+const MIGRATIONS: [fn(&mut Value) -> Result<(), SessionError>; SESSION_VERSION as usize - 1] = [];
+
+fn check_version(value: &Value) -> Result<u64, SessionError> {
+    let version = value.get("version").ok_or_else(|| SessionError("missing version".to_string()))?;
+    let version = version.as_u64().ok_or_else(|| SessionError(format!("bad version {}", version)))?;
+    if version == 0 {
+        return Err(SessionError("bad version 0".to_string()));
+    }
+    if version > SESSION_VERSION {
+        return Err(SessionError(format!("version {} is newer than {}", version, SESSION_VERSION)));
+    }
+    Ok(version)
+}
+
+/// Upgrades a session of an older version to `SESSION_VERSION`.
+fn migrate(value: &mut Value, version: u64) -> Result<(), SessionError> {
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        migration(value)?;
+    }
+    value["version"] = SESSION_VERSION.into();
+    Ok(())
+}
+
+impl Session {
+    pub fn new(game: Game) -> Self {
+        Session { version: SESSION_VERSION, speed: 500, view: View::default(), state: GameState::new(game) }
+    }
+
+    fn as_ref(&self) -> SessionRef<'_> {
+        SessionRef { version: SESSION_VERSION, speed: self.speed, view: &self.view, state: &self.state }
+    }
+
+    pub fn to_string(&self, format: SessionFormat) -> Result<String, SessionError> {
+        self.as_ref().to_string(format)
+    }
+
+    /// Reads a session, checking its version first and upgrading older ones.
+    pub fn from_str(text: &str, format: SessionFormat) -> Result<Session, SessionError> {
+        let mut value: Value = match format {
+            SessionFormat::Json => serde_json::from_str(text).map_err(|error| SessionError(error.to_string()))?,
+            SessionFormat::Toml => toml::from_str(text).map_err(|error| SessionError(error.to_string()))?,
+        };
+        let version = check_version(&value)?;
+        migrate(&mut value, version)?;
+        serde_json::from_value(value).map_err(|error| SessionError(error.to_string()))
+    }
+
+    /// Saves the session as JSON or TOML, depending on the file extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SessionError> {
+        self.as_ref().save(path.as_ref())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Session, SessionError> {
+        let path = path.as_ref();
+        let format = SessionFormat::from_path(path)?;
+        let text = fs::read_to_string(path).map_err(|error| SessionError(format!("{}: {}", path.display(), error)))?;
+        Session::from_str(&text, format)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+    use crate::node::Node;
+
+    fn session() -> Session {
+        let mut game = Game::from_rle("x = 3, y = 3, rule = B36/S23:T32,8\nbo$2bo$3o!").unwrap();
+        game.live_nodes.push(Node { x: -5, y: 3 });
+        let mut session = Session::new(game);
+        session.speed = 125;
+        session.view = View { x: 40., y: -12.5, zoom: 0.5 };
+        session.state.status = GameStatus::Play;
+        session.state.generation = 42;
+        session.state.grid = Grid::new(16, 12);
+        session
+    }
+
+    fn assert_same_session(copy: &Session, session: &Session) {
+        assert_eq!(copy.version, SESSION_VERSION);
+        assert_eq!(copy.speed, session.speed);
+        assert_eq!(copy.view, session.view);
+        assert_eq!(copy.state.status, session.state.status);
+        assert_eq!(copy.state.generation, session.state.generation);
+        assert_eq!(copy.state.grid.radius, session.state.grid.radius);
+        assert_eq!(copy.state.grid.cell_size, session.state.grid.cell_size);
+        assert_eq!(copy.state.grid.get_cells().len(), session.state.grid.get_cells().len());
+        assert_eq!(copy.state.game.rule, session.state.game.rule);
+        assert_eq!(copy.state.game.topology, session.state.game.topology);
+        assert_eq!(copy.state.game.get_sorted_cells(), session.state.game.get_sorted_cells());
+    }
+
+    #[test]
+    fn round_trips_session_through_json_and_toml() {
+        let session = session();
+        for format in [SessionFormat::Json, SessionFormat::Toml] {
+            let text = session.to_string(format).unwrap();
+            assert_same_session(&Session::from_str(&text, format).unwrap(), &session);
+        }
+        let json = session.to_string(SessionFormat::Json).unwrap();
+        assert!(json.contains("\"rule\": \"B36/S23\""));
+        assert!(json.contains("\"topology\": \"T32,8\""));
+    }
+
+    #[test]
+    fn rejects_newer_and_malformed_sessions() {
+        let game = serde_json::to_string(&session().state.game).unwrap();
+        assert_eq!(Session::from_str(&game, SessionFormat::Json).err(), Some(SessionError("missing version".to_string())));
+        let mut newer = serde_json::to_value(session()).unwrap();
+        newer["version"] = json!(SESSION_VERSION + 1);
+        assert!(Session::from_str(&newer.to_string(), SessionFormat::Json).is_err());
+        let mut bad_rule = serde_json::to_value(session()).unwrap();
+        bad_rule["state"]["game"]["rule"] = json!("B9/S23");
+        assert!(Session::from_str(&bad_rule.to_string(), SessionFormat::Json).is_err());
+        assert!(Session::from_str("version = ", SessionFormat::Toml).is_err());
+        assert!(SessionFormat::from_path(Path::new("session.yaml")).is_err());
+        let mut huge_grid = serde_json::to_value(session()).unwrap();
+        huge_grid["state"]["grid"]["radius"] = json!(i32::MAX);
+        assert!(Session::from_str(&huge_grid.to_string(), SessionFormat::Json).is_err());
+    }
+
+    #[test]
+    fn migrates_older_sessions_to_the_current_version() {
+        let current = serde_json::to_value(session()).unwrap();
+        let mut value = current.clone();
+        migrate(&mut value, 1).unwrap();
+        assert_eq!(value, current);
+        let mut first = current.clone();
+        first["version"] = json!(1);
+        assert_same_session(&Session::from_str(&first.to_string(), SessionFormat::Json).unwrap(), &session());
+        first["version"] = json!(0);
+        assert!(Session::from_str(&first.to_string(), SessionFormat::Json).is_err());
+    }
+
+    #[test]
+    fn saves_and_loads_files() {
+        let path = std::env::temp_dir().join(format!("game_of_life_session_{}.toml", std::process::id()));
+        session().save(&path).unwrap();
+        let copy = Session::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_same_session(&copy, &session());
+    }
+
+    #[cfg(feature = "wide-coordinates")]
+    #[test]
+    fn keeps_coordinates_past_64_bits() {
+        let mut session = session();
+        session.state.game.topology = crate::topology::Topology::Plane;
+        session.state.game.live_nodes.push(Node { x: i128::from(i64::MAX) * 4, y: i128::from(i64::MIN) * 4 });
+        for format in [SessionFormat::Json, SessionFormat::Toml] {
+            let text = session.to_string(format).unwrap();
+            assert_same_session(&Session::from_str(&text, format).unwrap(), &session);
+        }
+        let narrow = r#"{"version": 1, "speed": 500, "view": {"x": 0.0, "y": 0.0, "zoom": 1.0},
+            "state": {"game": {"live_nodes": [{"x": -3, "y": 4}], "topology": "", "rule": "B3/S23"},
+            "status": "Pause", "grid": {"radius": 10, "cell_size": 10}}}"#;
+        let copy = Session::from_str(narrow, SessionFormat::Json).unwrap();
+        assert_eq!(copy.state.game.live_nodes, vec![Node { x: -3, y: 4 }]);
+    }
+}
@@ -0,0 +1,175 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::game::Game;
+use crate::node::Coord;
+
+/// The state of one generation. Births and deaths are the cells that came
+/// alive or died since the previous record, and the bounds are those of the
+/// live cells, absent when there are none.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GenerationStats {
+    pub generation: u64,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+    pub left: Option<Coord>,
+    pub bottom: Option<Coord>,
+    pub right: Option<Coord>,
+    pub top: Option<Coord>,
+    /// The share of the bounding box that is alive.
+    pub density: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatsFormat {
+    Csv,
+    Json,
+}
+
+impl StatsFormat {
+    pub fn from_path(path: &Path) -> Result<StatsFormat, StatsError> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("csv") => Ok(StatsFormat::Csv),
+            Some("json") => Ok(StatsFormat::Json),
+            _ => Err(StatsError(format!("{} is not a .csv or .json file", path.display()))),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StatsError(String);
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "statistics error: {}", self.0)
+    }
+}
+
+impl std::error::Error for StatsError {}
+
+const CSV_HEADER: &str = "generation,population,births,deaths,left,bottom,right,top,density";
+
+/// The statistics of a run, one record per generation.
+#[derive(Default)]
+pub struct Statistics {
+    pub generations: Vec<GenerationStats>,
+    previous: HashSet<(Coord, Coord)>,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Statistics::default()
+    }
+
+    pub fn record(&mut self, generation: u64, game: &Game) {
+        let cells: HashSet<(Coord, Coord)> = game.live_nodes.iter().map(|node| (node.x, node.y)).collect();
+        let births = cells.difference(&self.previous).count();
+        let deaths = self.previous.difference(&cells).count();
+        let bounds = game.get_live_boundary();
+        let density = match &bounds {
+            Some(bounds) => {
+                let width = bounds.upper.x as f64 - bounds.lower.x as f64 + 1.;
+                let height = bounds.upper.y as f64 - bounds.lower.y as f64 + 1.;
+                cells.len() as f64 / (width * height)
+            }
+            None => 0.,
+        };
+        self.generations.push(GenerationStats {
+            generation,
+            population: cells.len(),
+            births,
+            deaths,
+            left: bounds.as_ref().map(|bounds| bounds.lower.x),
+            bottom: bounds.as_ref().map(|bounds| bounds.lower.y),
+            right: bounds.as_ref().map(|bounds| bounds.upper.x),
+            top: bounds.as_ref().map(|bounds| bounds.upper.y),
+            density,
+        });
+        self.previous = cells;
+    }
+
+    /// One line per generation after a header, with empty bounds when no
+    /// cells are alive.
+    pub fn to_csv(&self) -> String {
+        let coord = |coord: Option<Coord>| coord.map(|coord| coord.to_string()).unwrap_or_default();
+        let mut csv = format!("{}\n", CSV_HEADER);
+        for stats in &self.generations {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                stats.generation, stats.population, stats.births, stats.deaths,
+                coord(stats.left), coord(stats.bottom), coord(stats.right), coord(stats.top), stats.density,
+            ));
+        }
+        csv
+    }
+
+    pub fn to_json(&self) -> Result<String, StatsError> {
+        serde_json::to_string_pretty(&self.generations).map_err(|error| StatsError(error.to_string()))
+    }
+
+    pub fn to_text(&self, format: StatsFormat) -> Result<String, StatsError> {
+        match format {
+            StatsFormat::Csv => Ok(self.to_csv()),
+            StatsFormat::Json => self.to_json(),
+        }
+    }
+
+    /// Writes CSV or JSON depending on the extension of the path.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), StatsError> {
+        let path = path.as_ref();
+        let text = self.to_text(StatsFormat::from_path(path)?)?;
+        fs::write(path, text).map_err(|error| StatsError(format!("{}: {}", path.display(), error)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::node::Node;
+
+    #[test]
+    fn records_births_deaths_and_bounds() {
+        let mut game = Game::from_rle("3o!").unwrap();
+        let mut statistics = Statistics::new();
+        statistics.record(0, &game);
+        game.evolve();
+        statistics.record(1, &game);
+        let first = &statistics.generations[0];
+        assert_eq!((first.population, first.births, first.deaths), (3, 3, 0));
+        assert_eq!((first.left, first.bottom, first.right, first.top), (Some(0), Some(0), Some(2), Some(0)));
+        assert_eq!(first.density, 1.);
+        let second = &statistics.generations[1];
+        assert_eq!((second.generation, second.population, second.births, second.deaths), (1, 3, 2, 2));
+        assert_eq!((second.left, second.bottom, second.right, second.top), (Some(1), Some(-1), Some(1), Some(1)));
+    }
+
+    #[test]
+    fn measures_the_density_of_bounds_wider_than_coordinates() {
+        let mut game = Game::new();
+        game.live_nodes = vec![Node { x: Coord::MIN, y: 0 }, Node { x: Coord::MAX, y: 0 }];
+        let mut statistics = Statistics::new();
+        statistics.record(0, &game);
+        let density = statistics.generations[0].density;
+        assert!(density > 0. && density < 1e-18, "{}", density);
+    }
+
+    #[test]
+    fn writes_csv_and_json() {
+        let mut statistics = Statistics::new();
+        statistics.record(0, &Game::from_rle("2o$bo!").unwrap());
+        statistics.record(1, &Game::new());
+        assert_eq!(
+            statistics.to_csv(),
+            "generation,population,births,deaths,left,bottom,right,top,density\n\
+             0,3,3,0,0,0,1,1,0.75\n\
+             1,0,0,3,,,,,0\n",
+        );
+        let json: Vec<GenerationStats> = serde_json::from_str(&statistics.to_json().unwrap()).unwrap();
+        assert_eq!(json, statistics.generations);
+        assert_eq!(StatsFormat::from_path(Path::new("growth.csv")), Ok(StatsFormat::Csv));
+        assert!(StatsFormat::from_path(Path::new("growth.txt")).is_err());
+    }
+}
@@ -0,0 +1,11 @@
+use crate::node::Node;
+
+/// Asserts that both lists hold the same nodes, in any order.
+pub fn assert_same_nodes(actual: &[Node], expected: &[Node]) {
+    let sorted = |nodes: &[Node]| {
+        let mut cells: Vec<_> = nodes.iter().map(|node| (node.x, node.y)).collect();
+        cells.sort_unstable();
+        cells
+    };
+    assert_eq!(sorted(actual), sorted(expected));
+}
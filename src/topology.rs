@@ -0,0 +1,391 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use crate::box_boundary::BoxBoundary;
+use crate::node::{Coord, Node};
+
+/// The pair of opposite edges a twist or shift is applied to. `Horizontal`
+/// is the top and bottom edges, `Vertical` the left and right edges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edges {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Topology {
+    Plane,
+    Torus { width: Coord, height: Coord },
+    /// A torus where crossing one pair of edges also moves the node `shift`
+    /// cells along that edge.
+    ShiftedTorus { width: Coord, height: Coord, edges: Edges, shift: Coord },
+    /// A torus where crossing one pair of edges mirrors the node along that edge.
+    KleinBottle { width: Coord, height: Coord, twist: Edges },
+    /// Both pairs of edges are twisted.
+    CrossSurface { width: Coord, height: Coord },
+    /// The top edge is joined to the left edge and the bottom edge to the right edge.
+    Sphere { size: Coord },
+}
+
+/// The narrowest side of a bounded grid. Golly rejects narrower grids, where a
+/// cell's wrapped neighbors would repeat or be the cell itself.
+pub const MIN_SIZE: Coord = 3;
+
+impl Topology {
+    pub fn torus(width: Coord, height: Coord) -> Result<Self, ParseTopologyError> {
+        Topology::Torus { width, height }.validate()
+    }
+
+    /// The topology if every side of its grid is at least `MIN_SIZE` long.
+    pub fn validate(self) -> Result<Self, ParseTopologyError> {
+        match self.get_size() {
+            Some((width, height)) if width < MIN_SIZE || height < MIN_SIZE => {
+                Err(ParseTopologyError(format!("{} is narrower than {} cells", self, MIN_SIZE)))
+            }
+            _ => Ok(self),
+        }
+    }
+
+    pub fn get_size(&self) -> Option<(Coord, Coord)> {
+        match *self {
+            Topology::Plane => None,
+            Topology::Torus { width, height }
+            | Topology::ShiftedTorus { width, height, .. }
+            | Topology::KleinBottle { width, height, .. }
+            | Topology::CrossSurface { width, height } => Some((width, height)),
+            Topology::Sphere { size } => Some((size, size)),
+        }
+    }
+
+    pub fn get_bounds(&self) -> Option<BoxBoundary> {
+        let (width, height) = self.get_size()?;
+        let lower = Node { x: -(width / 2), y: -(height / 2) };
+        let upper = Node { x: lower.x + width - 1, y: lower.y + height - 1 };
+        Some(BoxBoundary { lower, upper })
+    }
+
+    pub fn contains(&self, node: &Node) -> bool {
+        match self.get_bounds() {
+            None => true,
+            Some(bounds) => bounds.contains(node),
+        }
+    }
+
+    /// Maps a node that may lie outside the grid onto the grid cell glued to it.
+    /// Returns `None` for the points past a sphere's corners, which have no
+    /// cell on the other side, and for every node outside a grid narrower
+    /// than `MIN_SIZE`, which `validate` rejects but code can still build.
+    pub fn wrap(&self, node: &Node) -> Option<Node> {
+        let bounds = match self.get_bounds() {
+            None => return Some(node.clone()),
+            Some(bounds) => bounds,
+        };
+        if bounds.contains(node) {
+            return Some(node.clone());
+        }
+        let (width, height) = self.get_size()?;
+        if width < MIN_SIZE || height < MIN_SIZE {
+            return None;
+        }
+        let u = node.x - bounds.lower.x;
+        let v = node.y - bounds.lower.y;
+        let (u, v) = match *self {
+            Topology::Plane | Topology::Torus { .. } => (u, v),
+            Topology::ShiftedTorus { edges: Edges::Horizontal, shift, .. } => {
+                (u + v.div_euclid(height) * shift, v)
+            }
+            Topology::ShiftedTorus { edges: Edges::Vertical, shift, .. } => {
+                (u, v + u.div_euclid(width) * shift)
+            }
+            Topology::KleinBottle { twist: Edges::Horizontal, .. } => {
+                (mirror_if_odd(u, width, v.div_euclid(height)), v)
+            }
+            Topology::KleinBottle { twist: Edges::Vertical, .. } => {
+                (u, mirror_if_odd(v, height, u.div_euclid(width)))
+            }
+            Topology::CrossSurface { .. } => (
+                mirror_if_odd(u, width, v.div_euclid(height)),
+                mirror_if_odd(v, height, u.div_euclid(width)),
+            ),
+            Topology::Sphere { size } => {
+                let outside_x = u < 0 || u >= size;
+                let outside_y = v < 0 || v >= size;
+                if outside_x && outside_y {
+                    return None;
+                }
+                let (u, v) = if v >= size {
+                    (v - size, size - 1 - u)
+                } else if u < 0 {
+                    (size - 1 - v, u + size)
+                } else if v < 0 {
+                    (size + v, size - 1 - u)
+                } else {
+                    (size - 1 - v, u - size)
+                };
+                if u < 0 || u >= size || v < 0 || v >= size {
+                    return None;
+                }
+                (u, v)
+            }
+        };
+        Some(Node {
+            x: u.rem_euclid(width) + bounds.lower.x,
+            y: v.rem_euclid(height) + bounds.lower.y,
+        })
+    }
+}
+
+fn mirror_if_odd(coordinate: Coord, length: Coord, crossings: Coord) -> Coord {
+    if crossings.rem_euclid(2) == 1 {
+        length - 1 - coordinate
+    } else {
+        coordinate
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Topology::Plane => Ok(()),
+            Topology::Torus { width, height } => write!(f, "T{},{}", width, height),
+            Topology::ShiftedTorus { width, height, edges: Edges::Horizontal, shift } => {
+                write!(f, "T{}{:+},{}", width, shift, height)
+            }
+            Topology::ShiftedTorus { width, height, edges: Edges::Vertical, shift } => {
+                write!(f, "T{},{}{:+}", width, height, shift)
+            }
+            Topology::KleinBottle { width, height, twist: Edges::Horizontal } => {
+                write!(f, "K{}*,{}", width, height)
+            }
+            Topology::KleinBottle { width, height, twist: Edges::Vertical } => {
+                write!(f, "K{},{}*", width, height)
+            }
+            Topology::CrossSurface { width, height } => write!(f, "C{},{}", width, height),
+            Topology::Sphere { size } => write!(f, "S{}", size),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseTopologyError(String);
+
+impl fmt::Display for ParseTopologyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid topology: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseTopologyError {}
+
+/// A single side length of a Golly bounded grid with its optional suffix,
+/// e.g. `64`, `64*` or `64+3`.
+struct Side {
+    length: Coord,
+    twisted: bool,
+    shift: Coord,
+}
+
+impl FromStr for Side {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(length) = s.strip_suffix('*') {
+            let length = length.trim().parse().map_err(|_| ())?;
+            return Ok(Side { length, twisted: true, shift: 0 });
+        }
+        match s.find(['+', '-']) {
+            Some(index) => {
+                let length = s[..index].trim().parse().map_err(|_| ())?;
+                let shift = s[index..].trim().parse().map_err(|_| ())?;
+                Ok(Side { length, twisted: false, shift })
+            }
+            None => {
+                let length = s.parse().map_err(|_| ())?;
+                Ok(Side { length, twisted: false, shift: 0 })
+            }
+        }
+    }
+}
+
+/// Parses the Golly bounded grid suffix, e.g. `T64,48` for a 64x48 torus,
+/// `T64+3,48` for a shifted torus, `K64*,48` for a Klein bottle, `C64,48`
+/// for a cross-surface or `S64` for a sphere.
+impl FromStr for Topology {
+    type Err = ParseTopologyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Ok(Topology::Plane);
+        }
+        let error = || ParseTopologyError(s.to_string());
+        let mut chars = s.chars();
+        let kind = chars.next().map(|c| c.to_ascii_uppercase());
+        let size = chars.as_str();
+        if kind == Some('S') {
+            let size: Coord = match size.split_once(',') {
+                Some((width, height)) if width.trim() == height.trim() => width.trim().parse(),
+                Some(_) => return Err(error()),
+                None => size.trim().parse(),
+            }.map_err(|_| error())?;
+            return Topology::Sphere { size }.validate();
+        }
+        let (width, height) = size.split_once(',').ok_or_else(error)?;
+        let width: Side = width.parse().map_err(|_| error())?;
+        let height: Side = height.parse().map_err(|_| error())?;
+        let (w, h) = (width.length, height.length);
+        let shifted = width.shift != 0 || height.shift != 0;
+        let topology = match kind {
+            Some('T') if width.twisted || height.twisted => Err(error()),
+            Some('T') if width.shift != 0 && height.shift != 0 => Err(error()),
+            Some('T') if width.shift != 0 => Ok(Topology::ShiftedTorus {
+                width: w, height: h, edges: Edges::Horizontal, shift: width.shift,
+            }),
+            Some('T') if height.shift != 0 => Ok(Topology::ShiftedTorus {
+                width: w, height: h, edges: Edges::Vertical, shift: height.shift,
+            }),
+            Some('T') => Ok(Topology::Torus { width: w, height: h }),
+            Some('K') if shifted || width.twisted == height.twisted => Err(error()),
+            Some('K') if width.twisted => Ok(Topology::KleinBottle {
+                width: w, height: h, twist: Edges::Horizontal,
+            }),
+            Some('K') => Ok(Topology::KleinBottle { width: w, height: h, twist: Edges::Vertical }),
+            Some('C') if shifted || width.twisted || height.twisted => Err(error()),
+            Some('C') => Ok(Topology::CrossSurface { width: w, height: h }),
+            _ => Err(error()),
+        };
+        topology?.validate()
+    }
+}
+
+/// Topologies are saved as their Golly suffix, the plane as an empty string.
+impl Serialize for Topology {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Topology {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn plane_does_not_wrap() {
+        let topology = Topology::Plane;
+        assert_eq!(topology.wrap(&Node { x: 1000, y: -1000 }), Some(Node { x: 1000, y: -1000 }));
+        assert!(topology.get_bounds().is_none());
+    }
+
+    #[test]
+    fn torus_bounds_are_centered() {
+        let bounds = Topology::Torus { width: 8, height: 5 }.get_bounds().unwrap();
+        assert_eq!(bounds.lower, Node { x: -4, y: -2 });
+        assert_eq!(bounds.upper, Node { x: 3, y: 2 });
+    }
+
+    #[test]
+    fn torus_wraps_across_opposite_edges() {
+        let topology = Topology::Torus { width: 8, height: 5 };
+        assert_eq!(topology.wrap(&Node { x: 4, y: 0 }), Some(Node { x: -4, y: 0 }));
+        assert_eq!(topology.wrap(&Node { x: -5, y: 0 }), Some(Node { x: 3, y: 0 }));
+        assert_eq!(topology.wrap(&Node { x: 0, y: 3 }), Some(Node { x: 0, y: -2 }));
+        assert_eq!(topology.wrap(&Node { x: 0, y: -3 }), Some(Node { x: 0, y: 2 }));
+        assert_eq!(topology.wrap(&Node { x: 4, y: 3 }), Some(Node { x: -4, y: -2 }));
+    }
+
+    #[test]
+    fn shifted_torus_shifts_along_the_crossed_edge() {
+        let topology = Topology::ShiftedTorus { width: 8, height: 8, edges: Edges::Horizontal, shift: 3 };
+        assert_eq!(topology.wrap(&Node { x: 0, y: 4 }), Some(Node { x: 3, y: -4 }));
+        assert_eq!(topology.wrap(&Node { x: 0, y: -5 }), Some(Node { x: -3, y: 3 }));
+        assert_eq!(topology.wrap(&Node { x: 4, y: 0 }), Some(Node { x: -4, y: 0 }));
+    }
+
+    #[test]
+    fn klein_bottle_mirrors_across_the_twisted_edges() {
+        let topology = Topology::KleinBottle { width: 8, height: 8, twist: Edges::Horizontal };
+        assert_eq!(topology.wrap(&Node { x: -4, y: 4 }), Some(Node { x: 3, y: -4 }));
+        assert_eq!(topology.wrap(&Node { x: 0, y: -5 }), Some(Node { x: -1, y: 3 }));
+        assert_eq!(topology.wrap(&Node { x: 4, y: 1 }), Some(Node { x: -4, y: 1 }));
+    }
+
+    #[test]
+    fn cross_surface_mirrors_across_both_edges() {
+        let topology = Topology::CrossSurface { width: 8, height: 8 };
+        assert_eq!(topology.wrap(&Node { x: -4, y: 4 }), Some(Node { x: 3, y: -4 }));
+        assert_eq!(topology.wrap(&Node { x: 4, y: 1 }), Some(Node { x: -4, y: -2 }));
+    }
+
+    #[test]
+    fn sphere_joins_adjacent_edges() {
+        let topology = Topology::Sphere { size: 8 };
+        // above the top row comes out of the left column
+        assert_eq!(topology.wrap(&Node { x: -4, y: 4 }), Some(Node { x: -4, y: 3 }));
+        assert_eq!(topology.wrap(&Node { x: -3, y: 4 }), Some(Node { x: -4, y: 2 }));
+        assert_eq!(topology.wrap(&Node { x: -5, y: 2 }), Some(Node { x: -3, y: 3 }));
+        // below the bottom row comes out of the right column
+        assert_eq!(topology.wrap(&Node { x: 3, y: -5 }), Some(Node { x: 3, y: -4 }));
+        assert_eq!(topology.wrap(&Node { x: 2, y: -5 }), Some(Node { x: 3, y: -3 }));
+        assert_eq!(topology.wrap(&Node { x: 4, y: -3 }), Some(Node { x: 2, y: -4 }));
+        assert_eq!(topology.wrap(&Node { x: -5, y: 4 }), None);
+    }
+
+    #[test]
+    fn parses_golly_bounded_grid_suffix() {
+        assert_eq!("T64,48".parse(), Ok(Topology::Torus { width: 64, height: 48 }));
+        assert_eq!("T64+3,48".parse(), Ok(Topology::ShiftedTorus {
+            width: 64, height: 48, edges: Edges::Horizontal, shift: 3,
+        }));
+        assert_eq!("T64,48-2".parse(), Ok(Topology::ShiftedTorus {
+            width: 64, height: 48, edges: Edges::Vertical, shift: -2,
+        }));
+        assert_eq!("K64*,48".parse(), Ok(Topology::KleinBottle {
+            width: 64, height: 48, twist: Edges::Horizontal,
+        }));
+        assert_eq!("K64,48*".parse(), Ok(Topology::KleinBottle {
+            width: 64, height: 48, twist: Edges::Vertical,
+        }));
+        assert_eq!("C64,48".parse(), Ok(Topology::CrossSurface { width: 64, height: 48 }));
+        assert_eq!("S64".parse(), Ok(Topology::Sphere { size: 64 }));
+        assert_eq!("".parse(), Ok(Topology::Plane));
+        assert!("T64".parse::<Topology>().is_err());
+        assert!("T0,4".parse::<Topology>().is_err());
+        assert!("K64,48".parse::<Topology>().is_err());
+        assert!("S64,48".parse::<Topology>().is_err());
+    }
+
+    #[test]
+    fn wraps_nothing_onto_grids_built_too_narrow() {
+        let topology = Topology::Torus { width: 0, height: 4 };
+        assert_eq!(topology.wrap(&Node { x: 5, y: 0 }), None);
+        assert!(Game::with_topology(topology).is_err());
+        assert!(Game::with_topology(Topology::Sphere { size: 3 }).is_ok());
+    }
+
+    #[test]
+    fn rejects_grids_narrower_than_three_cells() {
+        assert_eq!(Topology::torus(3, 3), Ok(Topology::Torus { width: 3, height: 3 }));
+        assert!(Topology::torus(2, 8).is_err());
+        assert!(Topology::torus(8, 1).is_err());
+        assert!(Topology::Sphere { size: 2 }.validate().is_err());
+        assert!(Topology::Plane.validate().is_ok());
+        for suffix in ["T2,8", "T8,1", "T-4,8", "K2*,8", "C8,2", "S2", "T2+1,8"] {
+            assert!(suffix.parse::<Topology>().is_err(), "{}", suffix);
+        }
+    }
+
+    #[test]
+    fn displays_golly_bounded_grid_suffix() {
+        for suffix in ["T64,48", "T64+3,48", "T64,48-2", "K64*,48", "K64,48*", "C64,48", "S64"] {
+            assert_eq!(suffix.parse::<Topology>().unwrap().to_string(), suffix);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn life(args: &[&str], input: &str) -> (String, bool) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_life"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    (String::from_utf8(output.stdout).unwrap(), output.status.success())
+}
+
+#[test]
+fn moves_a_glider() {
+    let (output, success) = life(&["-", "--generations", "4", "--quiet"], "x = 3, y = 3\nbo$2bo$3o!\n");
+    assert!(success);
+    assert_eq!(output, "#CXRLE Pos=1,-1\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n");
+}
+
+#[test]
+fn fails_on_a_malformed_pattern() {
+    let (output, success) = life(&["-", "--quiet"], "x = 3, y = 3\nbo$2bo$3?!\n");
+    assert!(!success);
+    assert!(output.is_empty());
+}
+
+#[test]
+fn records_statistics() {
+    let path = format!("{}/blinker.csv", env!("CARGO_TARGET_TMPDIR"));
+    let (_, success) = life(&["-", "--generations", "2", "--stats", &path, "--quiet"], "3o!\n");
+    assert!(success);
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "generation,population,births,deaths,left,bottom,right,top,density\n\
+         0,3,3,0,0,0,2,0,1\n\
+         1,3,2,2,1,-1,1,1,1\n\
+         2,3,2,2,0,0,2,0,1\n",
+    );
+}
+
+#[test]
+fn counts_the_objects_left() {
+    let (output, success) = life(&["-", "--generations", "8", "--inventory", "--quiet"], "2o8b3o$2o!\n");
+    assert!(success);
+    assert_eq!(output, "     1 blinker\n     1 block\n");
+}
+
+#[test]
+fn writes_an_animated_gif() {
+    let path = format!("{}/blinker.gif", env!("CARGO_TARGET_TMPDIR"));
+    let (_, success) = life(&["-", "--frames", "3", "--delay", "200", "--output", &path, "--quiet"], "3o!\n");
+    assert!(success);
+    let bytes = std::fs::read(&path).unwrap();
+    let mut decoder = gif::DecodeOptions::new().read_info(bytes.as_slice()).unwrap();
+    assert_eq!((decoder.width(), decoder.height()), (12, 12));
+    let mut frames = 0;
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        assert_eq!(frame.delay, 20);
+        frames += 1;
+    }
+    assert_eq!(frames, 3);
+}